@@ -0,0 +1,2 @@
+-- 标记用户下次登录后必须先修改密码（如使用默认密码的种子账户）
+ALTER TABLE users ADD COLUMN must_change_password BOOLEAN NOT NULL DEFAULT FALSE;
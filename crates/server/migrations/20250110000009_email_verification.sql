@@ -0,0 +1,19 @@
+ALTER TABLE users ADD COLUMN email_verified_at TIMESTAMPTZ;
+
+-- 此前只有通过 OIDC 创建的用户会写入邮箱，且均已由 IdP 验证
+UPDATE users SET email_verified_at = created_at
+WHERE email IS NOT NULL
+  AND EXISTS (SELECT 1 FROM user_identities WHERE user_identities.user_id = users.id);
+
+-- Create email_verification_tokens table
+-- 修改邮箱时先记录待验证的新地址，确认后才写入 users.email
+CREATE TABLE email_verification_tokens (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    email VARCHAR(255) NOT NULL,
+    token_hash VARCHAR(64) NOT NULL UNIQUE,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_email_verification_tokens_user_id ON email_verification_tokens(user_id);
@@ -0,0 +1,13 @@
+-- Create jwt_signing_keys table
+-- 最新的未退役密钥用于签名，退役密钥保留到其签发的令牌全部过期
+CREATE TABLE jwt_signing_keys (
+    kid VARCHAR(64) PRIMARY KEY,
+    algorithm VARCHAR(20) NOT NULL,
+    -- PKCS#8 PEM 格式的私钥
+    private_key TEXT NOT NULL,
+    public_jwk JSONB NOT NULL,
+    retired_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_jwt_signing_keys_created_at ON jwt_signing_keys(created_at);
@@ -0,0 +1,37 @@
+-- Create audit_events table
+-- 只追加的安全审计日志；不引用 users 表，用户删除后记录仍然保留
+CREATE TABLE audit_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    action VARCHAR(100) NOT NULL,
+    actor_id UUID,
+    -- 记录时的用户名，登录失败时为尝试的用户名
+    actor_name VARCHAR(255),
+    -- 管理员模拟登录时实际操作的管理员
+    impersonator_id UUID,
+    target_type VARCHAR(50),
+    target_id VARCHAR(255),
+    ip VARCHAR(64),
+    user_agent TEXT,
+    -- 变更前后的值或其他附加信息
+    diff JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_audit_events_created_at ON audit_events(created_at);
+CREATE INDEX idx_audit_events_action ON audit_events(action);
+CREATE INDEX idx_audit_events_actor_id ON audit_events(actor_id);
+CREATE INDEX idx_audit_events_target ON audit_events(target_type, target_id);
+
+CREATE FUNCTION audit_events_append_only() RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_events 只允许追加';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_events_no_update_delete
+    BEFORE UPDATE OR DELETE ON audit_events
+    FOR EACH ROW EXECUTE FUNCTION audit_events_append_only();
+
+CREATE TRIGGER audit_events_no_truncate
+    BEFORE TRUNCATE ON audit_events
+    FOR EACH STATEMENT EXECUTE FUNCTION audit_events_append_only();
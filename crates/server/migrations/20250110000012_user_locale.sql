@@ -0,0 +1,2 @@
+-- 用户界面语言偏好，如 zh-CN、en-US；为空时按请求的 Accept-Language 选择
+ALTER TABLE users ADD COLUMN locale VARCHAR(16);
@@ -1,4 +1,5 @@
 use anyhow::Result;
+use std::collections::HashMap;
 use std::env;
 
 #[derive(Debug, Clone)]
@@ -6,12 +7,24 @@ pub struct Config {
     pub server: ServerConfig,
     pub database: DatabaseConfig,
     pub jwt: JwtConfig,
+    pub password_hash: PasswordHashConfig,
+    pub oidc: Vec<OidcProviderConfig>,
+    pub ldap: Vec<LdapDirectoryConfig>,
+    pub webauthn: WebauthnConfig,
+    pub bootstrap: BootstrapConfig,
+    pub mail: MailConfig,
 }
 
 #[derive(Debug, Clone)]
 pub struct ServerConfig {
     pub port: u16,
     pub host: String,
+    /// 是否信任反向代理传入的 X-Forwarded-For / X-Real-IP
+    pub trust_proxy: bool,
+    /// 多层代理时内部代理的地址，从 X-Forwarded-For 右侧跳过这些地址
+    pub trusted_proxies: Vec<ipnet::IpNet>,
+    /// 前端访问地址，用于生成邮件中的链接
+    pub public_url: String,
 }
 
 #[derive(Debug, Clone)]
@@ -20,22 +33,258 @@ pub struct DatabaseConfig {
     pub max_connections: u32,
 }
 
+/// Argon2id 参数，修改后旧哈希会在用户下次登录时自动升级
+#[derive(Debug, Clone, Copy)]
+pub struct PasswordHashConfig {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JwtAlgorithm {
+    /// 使用 JWT_SECRET 共享密钥签名
+    Hs256,
+    /// Ed25519 非对称签名，公钥通过 JWKS 发布
+    EdDsa,
+    /// RSA 非对称签名，公钥通过 JWKS 发布
+    Rs256,
+}
+
 #[derive(Debug, Clone)]
 pub struct JwtConfig {
+    pub algorithm: JwtAlgorithm,
+    /// HS256 的签名密钥；使用非对称算法时仅用于验证切换前签发的令牌
     pub secret: String,
     pub expires_in: i64,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MailTransport {
+    Smtp,
+    /// 将邮件写入目录，便于开发和测试
+    File,
+    /// 只输出到日志
+    Log,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmtpTls {
+    None,
+    StartTls,
+    Tls,
+}
+
+#[derive(Debug, Clone)]
+pub struct MailConfig {
+    pub transport: MailTransport,
+    /// 发件人，如 `RikkaHub <noreply@example.com>`
+    pub from: String,
+    pub smtp_host: String,
+    pub smtp_port: Option<u16>,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    pub smtp_tls: SmtpTls,
+    pub file_dir: String,
+}
+
+/// 仅在首次启动初始化数据库时使用的配置
+#[derive(Debug, Clone)]
+pub struct BootstrapConfig {
+    /// 初始管理员密码；未设置时使用 `admin` 并要求首次登录后修改
+    pub admin_password: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WebauthnConfig {
+    /// Relying Party ID，通常为站点域名
+    pub rp_id: String,
+    /// 浏览器发起请求的来源，如 `https://rikkahub.example.com`
+    pub rp_origin: String,
+    pub rp_name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OidcProviderKind {
+    /// 标准 OpenID Connect（Authentik、Keycloak、Google 等）
+    Oidc,
+    /// GitHub 只支持 OAuth2，用户信息通过 REST API 获取
+    Github,
+}
+
+#[derive(Debug, Clone)]
+pub struct OidcProviderConfig {
+    /// 路由中使用的名称，如 `/auth/oidc/keycloak/authorize`
+    pub name: String,
+    pub kind: OidcProviderKind,
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub redirect_uri: String,
+    pub scopes: String,
+    /// ID Token 中携带用户组的 claim 名称
+    pub groups_claim: String,
+    /// IdP 用户组 -> RikkaHub 用户组
+    pub group_mapping: HashMap<String, String>,
+}
+
+impl OidcProviderConfig {
+    fn from_env(name: &str) -> Result<Self> {
+        let prefix = format!("OIDC_{}_", name.to_uppercase().replace('-', "_"));
+        let var = |key: &str| env::var(format!("{}{}", prefix, key));
+
+        let kind = match var("KIND").as_deref() {
+            Ok("github") => OidcProviderKind::Github,
+            _ => OidcProviderKind::Oidc,
+        };
+        let default_scopes = match kind {
+            OidcProviderKind::Oidc => "openid profile email",
+            OidcProviderKind::Github => "read:user user:email",
+        };
+
+        Ok(Self {
+            name: name.to_string(),
+            kind,
+            issuer: match kind {
+                OidcProviderKind::Oidc => var("ISSUER")
+                    .map_err(|_| anyhow::anyhow!("{}ISSUER 环境变量必须设置", prefix))?
+                    .trim_end_matches('/')
+                    .to_string(),
+                OidcProviderKind::Github => "https://github.com".to_string(),
+            },
+            client_id: var("CLIENT_ID")
+                .map_err(|_| anyhow::anyhow!("{}CLIENT_ID 环境变量必须设置", prefix))?,
+            client_secret: var("CLIENT_SECRET").unwrap_or_default(),
+            redirect_uri: var("REDIRECT_URI")
+                .map_err(|_| anyhow::anyhow!("{}REDIRECT_URI 环境变量必须设置", prefix))?,
+            scopes: var("SCOPES").unwrap_or_else(|_| default_scopes.to_string()),
+            groups_claim: var("GROUPS_CLAIM").unwrap_or_else(|_| "groups".to_string()),
+            // 格式: idp-group=rikkahub-group,idp-admins=admin
+            group_mapping: var("GROUP_MAPPING")
+                .unwrap_or_default()
+                .split(',')
+                .filter_map(|pair| pair.split_once('='))
+                .map(|(from, to)| (from.trim().to_string(), to.trim().to_string()))
+                .filter(|(from, to)| !from.is_empty() && !to.is_empty())
+                .collect(),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LdapDirectoryConfig {
+    pub name: String,
+    /// 如 `ldap://dc.corp.example.com:389` 或 `ldaps://...`
+    pub url: String,
+    pub starttls: bool,
+    /// 登录名中 `@` 之后的域名；为空时用于不带域名且没有本地密码的登录名
+    pub domains: Vec<String>,
+    /// 绑定 DN 模板，`{username}` 替换为登录名，
+    /// 如 `uid={username},ou=people,dc=example,dc=com` 或 AD 的 `{username}@corp.example.com`
+    pub bind_dn: String,
+    pub base_dn: String,
+    /// 绑定成功后查找用户条目的过滤器，如 `(sAMAccountName={username})`
+    pub user_filter: String,
+    pub username_attribute: String,
+    pub email_attribute: String,
+    /// 目录中的邮箱是否由管理员维护、可视为已验证；
+    /// 仅在用户无法自行修改邮箱属性时开启，否则可借此关联他人的本地账户
+    pub trust_email: bool,
+    pub name_attribute: String,
+    /// 存放用户所属组 DN 的属性
+    pub group_attribute: String,
+    /// 组 DN（小写）-> RikkaHub 用户组
+    pub group_mapping: HashMap<String, String>,
+}
+
+impl LdapDirectoryConfig {
+    fn from_env(name: &str) -> Result<Self> {
+        let prefix = format!("LDAP_{}_", name.to_uppercase().replace('-', "_"));
+        let var = |key: &str| env::var(format!("{}{}", prefix, key));
+
+        Ok(Self {
+            name: name.to_string(),
+            url: var("URL").map_err(|_| anyhow::anyhow!("{}URL 环境变量必须设置", prefix))?,
+            starttls: var("STARTTLS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(false),
+            domains: var("DOMAINS")
+                .unwrap_or_default()
+                .split(',')
+                .map(|d| d.trim().to_lowercase())
+                .filter(|d| !d.is_empty())
+                .collect(),
+            bind_dn: var("BIND_DN")
+                .map_err(|_| anyhow::anyhow!("{}BIND_DN 环境变量必须设置", prefix))?,
+            base_dn: var("BASE_DN")
+                .map_err(|_| anyhow::anyhow!("{}BASE_DN 环境变量必须设置", prefix))?,
+            user_filter: var("USER_FILTER").unwrap_or_else(|_| "(uid={username})".to_string()),
+            username_attribute: var("USERNAME_ATTRIBUTE").unwrap_or_else(|_| "uid".to_string()),
+            email_attribute: var("EMAIL_ATTRIBUTE").unwrap_or_else(|_| "mail".to_string()),
+            trust_email: var("TRUST_EMAIL")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(false),
+            name_attribute: var("NAME_ATTRIBUTE").unwrap_or_else(|_| "displayName".to_string()),
+            group_attribute: var("GROUP_ATTRIBUTE").unwrap_or_else(|_| "memberOf".to_string()),
+            // 组 DN 本身含有逗号，因此用分号分隔:
+            // cn=admins,ou=groups,dc=example,dc=com=admin;cn=staff,ou=groups,dc=example,dc=com=user
+            group_mapping: var("GROUP_MAPPING")
+                .unwrap_or_default()
+                .split(';')
+                .filter_map(|pair| pair.rsplit_once('='))
+                .map(|(from, to)| (from.trim().to_lowercase(), to.trim().to_string()))
+                .filter(|(from, to)| !from.is_empty() && !to.is_empty())
+                .collect(),
+        })
+    }
+
+    /// 记录在 `user_identities.provider` 中的名称，与 OIDC 提供方区分
+    pub fn identity_provider(&self) -> String {
+        format!("ldap:{}", self.name)
+    }
+}
+
 impl Config {
     pub fn from_env() -> Result<Self> {
         dotenvy::dotenv().ok();
 
+        let jwt_algorithm = match env::var("JWT_ALGORITHM").as_deref() {
+            Ok("EdDSA") => JwtAlgorithm::EdDsa,
+            Ok("RS256") => JwtAlgorithm::Rs256,
+            Ok("HS256") | Err(_) => JwtAlgorithm::Hs256,
+            Ok(other) => anyhow::bail!("不支持的 JWT_ALGORITHM: {}", other),
+        };
+
         Ok(Self {
             server: ServerConfig {
                 port: env::var("SERVER_PORT")
                     .unwrap_or_else(|_| "3000".to_string())
                     .parse()?,
                 host: env::var("SERVER_HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
+                trust_proxy: env::var("SERVER_TRUST_PROXY")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(false),
+                trusted_proxies: env::var("SERVER_TRUSTED_PROXIES")
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|entry| !entry.is_empty())
+                    .map(|entry| {
+                        entry
+                            .parse::<ipnet::IpNet>()
+                            .or_else(|_| entry.parse::<std::net::IpAddr>().map(ipnet::IpNet::from))
+                            .map_err(|_| {
+                                anyhow::anyhow!("无效的 SERVER_TRUSTED_PROXIES: {}", entry)
+                            })
+                    })
+                    .collect::<Result<_>>()?,
+                public_url: env::var("SERVER_PUBLIC_URL")
+                    .unwrap_or_else(|_| "http://localhost:3000".to_string())
+                    .trim_end_matches('/')
+                    .to_string(),
             },
             database: DatabaseConfig {
                 url: env::var("DATABASE_URL").unwrap_or_else(|_| {
@@ -48,13 +297,90 @@ impl Config {
                     .unwrap_or(10),
             },
             jwt: JwtConfig {
-                secret: env::var("JWT_SECRET")
-                    .expect("JWT_SECRET 环境变量必须设置"),
+                algorithm: jwt_algorithm,
+                secret: match jwt_algorithm {
+                    JwtAlgorithm::Hs256 => {
+                        env::var("JWT_SECRET").expect("JWT_SECRET 环境变量必须设置")
+                    }
+                    _ => env::var("JWT_SECRET").unwrap_or_default(),
+                },
                 expires_in: env::var("JWT_EXPIRES_IN")
                     .ok()
                     .and_then(|v| v.parse().ok())
                     .unwrap_or(86400), // 默认 24 小时
             },
+            password_hash: PasswordHashConfig {
+                memory_kib: env::var("ARGON2_MEMORY_KIB")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(argon2::Params::DEFAULT_M_COST),
+                iterations: env::var("ARGON2_ITERATIONS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(argon2::Params::DEFAULT_T_COST),
+                parallelism: env::var("ARGON2_PARALLELISM")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(argon2::Params::DEFAULT_P_COST),
+            },
+            oidc: env::var("OIDC_PROVIDERS")
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(OidcProviderConfig::from_env)
+                .collect::<Result<_>>()?,
+            ldap: env::var("LDAP_DIRECTORIES")
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(LdapDirectoryConfig::from_env)
+                .collect::<Result<_>>()?,
+            webauthn: WebauthnConfig {
+                rp_id: env::var("WEBAUTHN_RP_ID").unwrap_or_else(|_| "localhost".to_string()),
+                rp_origin: env::var("WEBAUTHN_RP_ORIGIN")
+                    .unwrap_or_else(|_| "http://localhost:3000".to_string()),
+                rp_name: env::var("WEBAUTHN_RP_NAME").unwrap_or_else(|_| "RikkaHub".to_string()),
+            },
+            mail: MailConfig {
+                transport: match env::var("MAIL_TRANSPORT").as_deref() {
+                    Ok("smtp") => MailTransport::Smtp,
+                    Ok("file") => MailTransport::File,
+                    _ => MailTransport::Log,
+                },
+                from: env::var("MAIL_FROM")
+                    .unwrap_or_else(|_| "RikkaHub <noreply@localhost>".to_string()),
+                smtp_host: env::var("SMTP_HOST").unwrap_or_else(|_| "localhost".to_string()),
+                smtp_port: env::var("SMTP_PORT").ok().and_then(|v| v.parse().ok()),
+                smtp_username: env::var("SMTP_USERNAME").ok().filter(|v| !v.is_empty()),
+                smtp_password: env::var("SMTP_PASSWORD").ok().filter(|v| !v.is_empty()),
+                smtp_tls: match env::var("SMTP_TLS").as_deref() {
+                    Ok("none") => SmtpTls::None,
+                    Ok("tls") => SmtpTls::Tls,
+                    _ => SmtpTls::StartTls,
+                },
+                file_dir: env::var("MAIL_FILE_DIR").unwrap_or_else(|_| "./mail".to_string()),
+            },
+            bootstrap: BootstrapConfig {
+                admin_password: env::var("ADMIN_PASSWORD").ok().filter(|v| !v.is_empty()),
+            },
         })
     }
+
+    pub fn oidc_provider(&self, name: &str) -> Option<&OidcProviderConfig> {
+        self.oidc.iter().find(|p| p.name == name)
+    }
+
+    /// 按登录名中的域名查找 LDAP 目录，返回目录和去掉域名后的用户名
+    pub fn ldap_directory_for<'a>(
+        &self,
+        login: &'a str,
+    ) -> Option<(&LdapDirectoryConfig, &'a str)> {
+        let (username, domain) = login.rsplit_once('@')?;
+        self.ldap
+            .iter()
+            .find(|d| d.domains.iter().any(|x| x.eq_ignore_ascii_case(domain)))
+            .map(|d| (d, username))
+    }
 }
@@ -1,8 +1,13 @@
 use anyhow::Result;
 use sqlx::{PgPool, postgres::PgPoolOptions};
 
-use crate::config::DatabaseConfig;
-use crate::utils::hash_password;
+use crate::config::{BootstrapConfig, DatabaseConfig};
+use crate::i18n::Locale;
+use crate::services::PasswordPolicy;
+use crate::utils::{hash_password, verify_password};
+
+/// 未指定初始管理员密码时使用的默认密码
+const DEFAULT_ADMIN_PASSWORD: &str = "admin";
 
 pub async fn create_pool(config: &DatabaseConfig) -> Result<PgPool> {
     let pool = PgPoolOptions::new()
@@ -18,7 +23,7 @@ pub async fn run_migrations(pool: &PgPool) -> Result<()> {
     Ok(())
 }
 
-pub async fn seed(pool: &PgPool) -> Result<()> {
+pub async fn seed(pool: &PgPool, bootstrap: &BootstrapConfig) -> Result<()> {
     // 检查是否已经有数据
     let user_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM users")
         .fetch_one(pool)
@@ -26,9 +31,26 @@ pub async fn seed(pool: &PgPool) -> Result<()> {
 
     if user_count.0 > 0 {
         tracing::info!("数据库已有数据，跳过 seed");
-        return Ok(());
+        return require_default_admin_password_change(pool).await;
     }
 
+    // 先校验初始管理员密码，避免写入部分数据后才失败
+    let admin_password = match &bootstrap.admin_password {
+        Some(password) => {
+            PasswordPolicy::default()
+                .validate(password, "admin")
+                .map_err(|msg| {
+                    anyhow::anyhow!(
+                        "初始管理员密码不符合要求: {}",
+                        msg.render(Locale::default())
+                    )
+                })?;
+            password.as_str()
+        }
+        None => DEFAULT_ADMIN_PASSWORD,
+    };
+    let must_change_password = bootstrap.admin_password.is_none();
+
     tracing::info!("开始初始化种子数据...");
 
     // 创建用户组: users 和 admin
@@ -54,16 +76,17 @@ pub async fn seed(pool: &PgPool) -> Result<()> {
 
     tracing::info!("用户组创建完成: users, admin");
 
-    // 创建 admin 用户
-    let password_hash = hash_password("admin")?;
+    // 创建 admin 用户，使用默认密码时要求首次登录后修改
+    let password_hash = hash_password(admin_password)?;
     let admin_user_id: (uuid::Uuid,) = sqlx::query_as(
         r#"
-        INSERT INTO users (username, nickname, password_hash, status)
-        VALUES ('admin', 'Administrator', $1, 1)
+        INSERT INTO users (username, nickname, password_hash, status, must_change_password)
+        VALUES ('admin', 'Administrator', $1, 1, $2)
         RETURNING id
         "#,
     )
     .bind(&password_hash)
+    .bind(must_change_password)
     .fetch_one(pool)
     .await?;
 
@@ -96,7 +119,35 @@ pub async fn seed(pool: &PgPool) -> Result<()> {
     .await?;
 
     tracing::info!("admin 组权限设置完成");
-    tracing::info!("种子数据初始化完成！默认账户: admin/admin");
+    if must_change_password {
+        tracing::warn!("种子数据初始化完成！默认账户: admin/admin，首次登录后必须修改密码");
+    } else {
+        tracing::info!("种子数据初始化完成！管理员账户: admin");
+    }
+
+    Ok(())
+}
+
+/// 早期版本初始化的 admin 账户未要求修改默认密码，启动时补上该要求
+async fn require_default_admin_password_change(pool: &PgPool) -> Result<()> {
+    let admin: Option<(uuid::Uuid, Option<String>)> = sqlx::query_as(
+        "SELECT id, password_hash FROM users WHERE username = 'admin' AND must_change_password = FALSE",
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    let Some((admin_id, Some(password_hash))) = admin else {
+        return Ok(());
+    };
+    if !verify_password(DEFAULT_ADMIN_PASSWORD, &password_hash)? {
+        return Ok(());
+    }
+
+    sqlx::query("UPDATE users SET must_change_password = TRUE, updated_at = NOW() WHERE id = $1")
+        .bind(admin_id)
+        .execute(pool)
+        .await?;
 
+    tracing::warn!("admin 账户仍在使用默认密码，已要求下次登录后修改密码");
     Ok(())
 }
@@ -0,0 +1,236 @@
+use axum::{
+    Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+
+use crate::{
+    i18n::{Locale, Message},
+    middleware::request_id,
+};
+
+/// 稳定的机器可读错误码，客户端应依据它而不是提示文字处理错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    BadRequest,
+    /// 请求字段校验失败，`details` 中给出具体字段
+    ValidationFailed,
+    Unauthorized,
+    InvalidCredentials,
+    MissingToken,
+    InvalidToken,
+    TokenExpired,
+    SessionRevoked,
+    InvalidMfaCode,
+    Forbidden,
+    PermissionDenied,
+    AccountDisabled,
+    /// 需先完成 `required_action` 指定的操作
+    ActionRequired,
+    EmailNotVerified,
+    IpNotAllowed,
+    ImpersonationForbidden,
+    NotFound,
+    Conflict,
+    RateLimited,
+    UpstreamError,
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn status(self) -> StatusCode {
+        match self {
+            Self::BadRequest | Self::ValidationFailed => StatusCode::BAD_REQUEST,
+            Self::Unauthorized
+            | Self::InvalidCredentials
+            | Self::MissingToken
+            | Self::InvalidToken
+            | Self::TokenExpired
+            | Self::SessionRevoked
+            | Self::InvalidMfaCode => StatusCode::UNAUTHORIZED,
+            Self::Forbidden
+            | Self::PermissionDenied
+            | Self::AccountDisabled
+            | Self::ActionRequired
+            | Self::EmailNotVerified
+            | Self::IpNotAllowed
+            | Self::ImpersonationForbidden => StatusCode::FORBIDDEN,
+            Self::NotFound => StatusCode::NOT_FOUND,
+            Self::Conflict => StatusCode::CONFLICT,
+            Self::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            Self::UpstreamError => StatusCode::BAD_GATEWAY,
+            Self::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// 字段级校验错误
+#[derive(Debug, Clone)]
+pub struct FieldError {
+    pub field: String,
+    pub message: Message,
+}
+
+/// 所有接口统一的错误类型，响应格式为
+/// `{ "error": 提示, "code": 错误码, "details": [...], "request_id": ... }`，
+/// 提示文字按当前请求的语言渲染
+#[derive(Debug)]
+pub struct AppError {
+    pub status: StatusCode,
+    pub code: ErrorCode,
+    pub message: Message,
+    pub details: Vec<FieldError>,
+    /// 合并到响应中的附加字段，如 `retry_after`；装箱以免 `Result<_, AppError>` 过大
+    pub extra: Box<Map<String, Value>>,
+}
+
+impl AppError {
+    pub fn new(code: ErrorCode, message: impl Into<Message>) -> Self {
+        Self {
+            status: code.status(),
+            code,
+            message: message.into(),
+            details: Vec::new(),
+            extra: Box::default(),
+        }
+    }
+
+    pub fn bad_request(message: impl Into<Message>) -> Self {
+        Self::new(ErrorCode::BadRequest, message)
+    }
+
+    /// 单个字段校验失败
+    pub fn validation(field: &str, message: impl Into<Message>) -> Self {
+        let message = message.into();
+        Self::new(ErrorCode::ValidationFailed, message.clone()).with_field(field, message)
+    }
+
+    pub fn unauthorized(message: impl Into<Message>) -> Self {
+        Self::new(ErrorCode::Unauthorized, message)
+    }
+
+    pub fn forbidden(message: impl Into<Message>) -> Self {
+        Self::new(ErrorCode::Forbidden, message)
+    }
+
+    pub fn not_found(message: impl Into<Message>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn conflict(message: impl Into<Message>) -> Self {
+        Self::new(ErrorCode::Conflict, message)
+    }
+
+    pub fn upstream(message: impl Into<Message>) -> Self {
+        Self::new(ErrorCode::UpstreamError, message)
+    }
+
+    /// 记录错误详情，响应中只返回通用提示，可凭 `request_id` 在日志中查找详情
+    pub fn internal(e: impl std::fmt::Display) -> Self {
+        tracing::error!(
+            request_id = request_id::current().as_deref().unwrap_or("-"),
+            "内部错误: {}",
+            e
+        );
+        Self::new(ErrorCode::Internal, "error.internal")
+    }
+
+    /// 覆盖错误码默认对应的状态码
+    pub fn with_status(mut self, status: StatusCode) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn with_field(mut self, field: &str, message: impl Into<Message>) -> Self {
+        self.details.push(FieldError {
+            field: field.to_string(),
+            message: message.into(),
+        });
+        self
+    }
+
+    pub fn with_extra(mut self, key: &str, value: impl Serialize) -> Self {
+        self.extra.insert(key.to_string(), json!(value));
+        self
+    }
+}
+
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?}: {}",
+            self.code,
+            self.message.render(Locale::default())
+        )
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let locale = Locale::current();
+        let mut body = *self.extra;
+        body.insert("error".to_string(), json!(self.message.render(locale)));
+        body.insert("code".to_string(), json!(self.code));
+        if !self.details.is_empty() {
+            let details: Vec<Value> = self
+                .details
+                .iter()
+                .map(|d| json!({ "field": d.field, "message": d.message.render(locale) }))
+                .collect();
+            body.insert("details".to_string(), json!(details));
+        }
+        if let Some(request_id) = request_id::current() {
+            body.insert("request_id".to_string(), json!(request_id));
+        }
+
+        (self.status, Json(Value::Object(body))).into_response()
+    }
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(e: sqlx::Error) -> Self {
+        match &e {
+            sqlx::Error::RowNotFound => Self::not_found("error.not_found"),
+            sqlx::Error::Database(db) if db.is_unique_violation() => {
+                tracing::debug!("唯一约束冲突: {}", db);
+                Self::conflict("error.conflict")
+            }
+            _ => Self::internal(e),
+        }
+    }
+}
+
+impl From<anyhow::Error> for AppError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::internal(format!("{:#}", e))
+    }
+}
+
+impl From<shared::CoreError> for AppError {
+    fn from(e: shared::CoreError) -> Self {
+        use shared::CoreError;
+
+        match e {
+            CoreError::ModelNotFound(model) => {
+                Self::not_found(Message::new("error.model_not_found").arg("model", model))
+            }
+            CoreError::ConversationNotFound(id) => {
+                Self::not_found(Message::new("error.conversation_not_found").arg("id", id))
+            }
+            CoreError::RequestFailed(detail) => {
+                // 上游返回的内容可能包含服务商内部信息，只记录日志
+                tracing::warn!(
+                    request_id = request_id::current().as_deref().unwrap_or("-"),
+                    "上游请求失败: {}",
+                    detail
+                );
+                Self::upstream("error.upstream_failed")
+            }
+            CoreError::Serialization(_) | CoreError::Internal(_) => Self::internal(e),
+        }
+    }
+}
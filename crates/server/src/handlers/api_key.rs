@@ -0,0 +1,139 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, ErrorCode},
+    handlers::{forbid_impersonation, require_session},
+    i18n::Message,
+    middleware::{AppState, AuthUser, Json, Path, RequestMeta},
+    models::ApiKey,
+    services::{ApiKeyService, AuditService, PermissionService, audit},
+    utils::api_key::{generate_api_key, hash_api_key},
+};
+
+#[derive(Debug, Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    pub scopes: Vec<String>,
+    #[serde(default)]
+    pub allowed_ips: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreateApiKeyResponse {
+    /// 完整的 key，仅在创建时返回一次
+    pub key: String,
+    pub api_key: ApiKey,
+}
+
+pub async fn list_api_keys(State(state): State<AppState>, auth: AuthUser) -> impl IntoResponse {
+    require_session(&auth)?;
+
+    let keys = ApiKeyService::list_by_user(&state.pool, auth.user_id).await?;
+
+    Ok::<_, AppError>(Json(keys))
+}
+
+pub async fn create_api_key(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    meta: RequestMeta,
+    Json(payload): Json<CreateApiKeyRequest>,
+) -> impl IntoResponse {
+    require_session(&auth)?;
+    forbid_impersonation(&auth)?;
+
+    let name = payload.name.trim();
+    if name.is_empty() || name.chars().count() > 100 {
+        return Err(AppError::validation("name", "error.invalid_name"));
+    }
+
+    if payload.scopes.is_empty() {
+        return Err(AppError::validation("scopes", "api_key.scopes_required"));
+    }
+
+    let granted = PermissionService::list_user_permissions(&state.pool, auth.user_id).await?;
+    if let Some(scope) = payload
+        .scopes
+        .iter()
+        .find(|scope| !PermissionService::is_granted(&granted, scope))
+    {
+        return Err(AppError::new(
+            ErrorCode::PermissionDenied,
+            Message::new("api_key.scope_not_granted").arg("scope", scope),
+        ));
+    }
+
+    if let Some(entry) = payload.allowed_ips.iter().find(|entry| {
+        entry.parse::<ipnet::IpNet>().is_err() && entry.parse::<std::net::IpAddr>().is_err()
+    }) {
+        return Err(AppError::validation(
+            "allowed_ips",
+            Message::new("api_key.invalid_ip").arg("entry", entry),
+        ));
+    }
+
+    if payload.expires_at.is_some_and(|t| t <= Utc::now()) {
+        return Err(AppError::validation(
+            "expires_at",
+            "api_key.expires_at_in_past",
+        ));
+    }
+
+    let (key, prefix) = generate_api_key();
+    let mut api_key = ApiKey::new(
+        auth.user_id,
+        name.to_string(),
+        prefix,
+        hash_api_key(&key),
+        payload.scopes,
+    );
+    api_key.allowed_ips = payload.allowed_ips;
+    api_key.expires_at = payload.expires_at;
+
+    let api_key = ApiKeyService::create(&state.pool, &api_key).await?;
+
+    let event = auth
+        .audit_event(&meta, audit::action::API_KEY_CREATE)
+        .target("api_key", api_key.id)
+        .diff(json!({
+            "name": api_key.name,
+            "prefix": api_key.prefix,
+            "scopes": api_key.scopes,
+            "allowed_ips": api_key.allowed_ips,
+            "expires_at": api_key.expires_at,
+        }));
+    AuditService::record(&state.pool, event).await;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateApiKeyResponse { key, api_key }),
+    ))
+}
+
+pub async fn delete_api_key(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    meta: RequestMeta,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    require_session(&auth)?;
+    forbid_impersonation(&auth)?;
+
+    let revoked = ApiKeyService::revoke(&state.pool, auth.user_id, id).await?;
+
+    if !revoked {
+        return Err(AppError::not_found("api_key.not_found"));
+    }
+
+    let event = auth
+        .audit_event(&meta, audit::action::API_KEY_DELETE)
+        .target("api_key", id);
+    AuditService::record(&state.pool, event).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
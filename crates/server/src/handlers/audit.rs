@@ -0,0 +1,132 @@
+use axum::{extract::State, http::header, response::IntoResponse};
+use serde::Serialize;
+
+use crate::{
+    error::AppError,
+    handlers::require_permission,
+    middleware::{AppState, AuthUser, Json, Query},
+    models::AuditEvent,
+    services::{AuditService, audit::AuditQuery},
+};
+
+const PERMISSION: &str = "admin.audit";
+
+/// 单页查询的最大条数
+const MAX_PAGE_SIZE: i64 = 500;
+/// 单次导出的最大条数
+const MAX_EXPORT_ROWS: i64 = 10_000;
+
+#[derive(Debug, Serialize)]
+pub struct AuditEventPage {
+    pub events: Vec<AuditEvent>,
+    pub total: i64,
+}
+
+pub async fn list_audit_events(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Query(query): Query<AuditQuery>,
+) -> impl IntoResponse {
+    require_permission(&state, &auth, PERMISSION).await?;
+
+    let events = AuditService::list(&state.pool, &query, MAX_PAGE_SIZE).await?;
+    let total = AuditService::count(&state.pool, &query).await?;
+
+    Ok::<_, AppError>(Json(AuditEventPage { events, total }))
+}
+
+/// 以 CSV 导出符合条件的审计事件
+pub async fn export_audit_events(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Query(query): Query<AuditQuery>,
+) -> impl IntoResponse {
+    require_permission(&state, &auth, PERMISSION).await?;
+
+    let events = AuditService::list(&state.pool, &query, MAX_EXPORT_ROWS).await?;
+
+    let mut csv = String::from(
+        "id,created_at,action,actor_id,actor_name,impersonator_id,target_type,target_id,ip,user_agent,diff\r\n",
+    );
+    for event in &events {
+        let fields = [
+            event.id.to_string(),
+            event.created_at.to_rfc3339(),
+            event.action.clone(),
+            event.actor_id.map(|id| id.to_string()).unwrap_or_default(),
+            event.actor_name.clone().unwrap_or_default(),
+            event
+                .impersonator_id
+                .map(|id| id.to_string())
+                .unwrap_or_default(),
+            event.target_type.clone().unwrap_or_default(),
+            event.target_id.clone().unwrap_or_default(),
+            event.ip.clone().unwrap_or_default(),
+            event.user_agent.clone().unwrap_or_default(),
+            event
+                .diff
+                .as_ref()
+                .map(|diff| diff.to_string())
+                .unwrap_or_default(),
+        ];
+        let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        csv.push_str(&row.join(","));
+        csv.push_str("\r\n");
+    }
+
+    Ok::<_, AppError>((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"audit-events.csv\"",
+            ),
+        ],
+        csv,
+    ))
+}
+
+/// 按 RFC 4180 转义字段；以公式字符开头的值加上 `'`，防止在表格软件中被执行
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_values_are_unchanged() {
+        assert_eq!(csv_field("alice"), "alice");
+        assert_eq!(csv_field(""), "");
+    }
+
+    #[test]
+    fn quotes_special_characters() {
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("line1\nline2"), "\"line1\nline2\"");
+    }
+
+    #[test]
+    fn neutralizes_formula_prefixes() {
+        for value in ["=1+1", "+1", "-1", "@SUM(A1)", "\tx"] {
+            assert_eq!(csv_field(value), format!("'{}", value));
+        }
+        assert_eq!(
+            csv_field("=HYPERLINK(\"http://x\",\"y\")"),
+            "\"'=HYPERLINK(\"\"http://x\"\",\"\"y\"\")\""
+        );
+        assert_eq!(csv_field("\rx"), "\"'\rx\"");
+    }
+}
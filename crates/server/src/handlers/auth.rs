@@ -1,14 +1,34 @@
-use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
+};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use uuid::Uuid;
 
 use crate::{
-    middleware::{AppState, AuthUser},
+    config::LdapDirectoryConfig,
+    error::{AppError, ErrorCode},
+    handlers::two_factor::{too_many_attempts, verify_second_factor_throttled},
+    middleware::{AppState, Json, RequestMeta, RequiredAction, RestrictedAuthUser},
     models::{User, UserStatus},
-    services::UserService,
-    utils::{jwt::encode_token, verify_password},
+    services::{
+        AuditService, IdentityService, JwtKeyService, LdapService, LoginThrottleService,
+        MfaChallengeService, TotpService, UserService, audit,
+        login_throttle::{self, LockoutPolicy, Reservation},
+    },
+    utils::{
+        dummy_password_hash, hash_password,
+        jwt::{TokenPurpose, encode_mfa_challenge_token, encode_token, encode_token_with_purpose},
+        password_needs_rehash, verify_password,
+    },
 };
 
+/// 两步验证挑战令牌的有效期（秒）
+const MFA_CHALLENGE_EXPIRES_IN: i64 = 300;
+/// 绑定两步验证专用令牌的有效期（秒）
+const MFA_ENROLLMENT_EXPIRES_IN: i64 = 900;
+
 #[derive(Debug, Deserialize)]
 pub struct LoginRequest {
     pub username: String,
@@ -19,80 +39,373 @@ pub struct LoginRequest {
 pub struct LoginResponse {
     pub token: String,
     pub user: User,
+    /// 非空时 token 为受限令牌，只能用于完成这些操作
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub required_actions: Vec<RequiredAction>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MfaChallengeResponse {
+    pub mfa_required: bool,
+    pub mfa_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TwoFactorLoginRequest {
+    pub mfa_token: String,
+    pub code: Option<String>,
+    pub recovery_code: Option<String>,
 }
 
 pub async fn login(
     State(state): State<AppState>,
+    meta: RequestMeta,
     Json(payload): Json<LoginRequest>,
 ) -> impl IntoResponse {
-    let user: Option<User> = UserService::find_by_username(&state.pool, &payload.username)
-        .await
-        .map_err(|e| {
-            tracing::error!("数据库查询失败: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({ "error": "内部服务器错误" })),
-            )
-        })?;
+    let ip = meta.ip;
+    let failed = |reason: &str| {
+        meta.audit_event(audit::action::LOGIN_FAILED)
+            .actor_name(&payload.username)
+            .diff(json!({ "reason": reason }))
+    };
+
+    let policy = LockoutPolicy::load(&state.pool).await?;
+    let attempt =
+        match LoginThrottleService::reserve(&state.pool, &policy, &payload.username, ip).await? {
+            Reservation::Granted(attempt) => attempt,
+            Reservation::Locked(retry_after) => {
+                AuditService::record(&state.pool, failed("locked")).await;
+                return Err(too_many_attempts(retry_after));
+            }
+        };
+
+    let user = match authenticate(&state, &payload.username, &payload.password).await {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            attempt.failed();
+            AuditService::record(&state.pool, failed("invalid_credentials")).await;
 
-    let user = match user {
-        Some(u) => u,
-        None => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({ "error": "用户名或密码错误" })),
+            return Err(AppError::new(
+                ErrorCode::InvalidCredentials,
+                "auth.invalid_credentials",
             ));
         }
+        Err(e) => {
+            LoginThrottleService::release(&state.pool, attempt).await?;
+            return Err(e);
+        }
     };
+    LoginThrottleService::release(&state.pool, attempt).await?;
 
     if user.status != UserStatus::Active as i16 {
-        return Err((
-            StatusCode::FORBIDDEN,
-            Json(json!({ "error": "用户已被禁用" })),
-        ));
+        AuditService::record(&state.pool, failed("disabled").actor(&user)).await;
+        return Err(AppError::new(ErrorCode::AccountDisabled, "user.disabled"));
     }
 
-    let password_hash = match &user.password_hash {
-        Some(hash) => hash,
-        None => {
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(json!({ "error": "用户名或密码错误" })),
-            ));
+    complete_login(
+        &state,
+        &meta,
+        user,
+        "password",
+        false,
+        Some(&payload.username),
+    )
+    .await
+}
+
+/// 依次尝试各认证后端，返回 `None` 表示用户名或密码错误
+///
+/// 登录名带有已配置的域名时只交给对应的 LDAP 目录；否则先校验本地密码，
+/// 本地用户不存在或未设置密码时再尝试未限定域名的目录
+async fn authenticate(
+    state: &AppState,
+    username: &str,
+    password: &str,
+) -> Result<Option<User>, AppError> {
+    if let Some((directory, name)) = state.config.ldap_directory_for(username) {
+        return ldap_authenticate(state, directory, name, password).await;
+    }
+
+    let user = UserService::find_by_username(&state.pool, username).await?;
+
+    if let Some(user) = user
+        && let Some(password_hash) = user.password_hash.as_deref()
+    {
+        let is_valid = verify_password(password, password_hash)?;
+        if !is_valid {
+            return Ok(None);
+        }
+
+        if password_needs_rehash(password_hash) {
+            upgrade_password_hash(state, &user, password_hash, password).await;
         }
+
+        return Ok(Some(user));
+    }
+
+    let directories: Vec<&LdapDirectoryConfig> = state
+        .config
+        .ldap
+        .iter()
+        .filter(|d| d.domains.is_empty())
+        .collect();
+    if directories.is_empty() {
+        // 没有可尝试的目录时仍对占位哈希做一次校验，使响应时间不泄露用户名是否存在
+        verify_password(password, dummy_password_hash())?;
+        return Ok(None);
+    }
+
+    for directory in directories {
+        if let Some(user) = ldap_authenticate(state, directory, username, password).await? {
+            return Ok(Some(user));
+        }
+    }
+
+    Ok(None)
+}
+
+/// 通过 LDAP 绑定验证密码，首次登录时自动创建本地用户并同步组映射
+async fn ldap_authenticate(
+    state: &AppState,
+    directory: &LdapDirectoryConfig,
+    username: &str,
+    password: &str,
+) -> Result<Option<User>, AppError> {
+    let identity = LdapService::authenticate(directory, username, password)
+        .await
+        .map_err(|e| {
+            tracing::error!("LDAP 目录 {} 认证失败: {}", directory.name, e);
+            AppError::upstream("auth.directory_unavailable")
+        })?;
+    let Some(identity) = identity else {
+        return Ok(None);
+    };
+
+    let user = IdentityService::resolve_user(
+        &state.pool,
+        &directory.identity_provider(),
+        &directory.group_mapping,
+        &identity,
+    )
+    .await
+    .map_err(|e| AppError::internal(format!("LDAP 用户关联失败: {:#}", e)))?;
+
+    Ok(Some(user))
+}
+
+/// 登录成功后用当前参数重新哈希密码，失败只记录日志而不影响登录
+async fn upgrade_password_hash(state: &AppState, user: &User, old_hash: &str, password: &str) {
+    let result = match hash_password(password) {
+        Ok(new_hash) => UserService::rehash_password(&state.pool, user.id, old_hash, &new_hash)
+            .await
+            .map_err(anyhow::Error::from),
+        Err(e) => Err(e),
     };
 
-    let is_valid = verify_password(&payload.password, password_hash).map_err(|e| {
-        tracing::error!("密码验证失败: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "error": "内部服务器错误" })),
-        )
-    })?;
-
-    if !is_valid {
-        return Err((
-            StatusCode::UNAUTHORIZED,
-            Json(json!({ "error": "用户名或密码错误" })),
+    match result {
+        Ok(()) => tracing::info!("已升级用户 {} 的密码哈希", user.username),
+        Err(e) => tracing::warn!("升级用户 {} 的密码哈希失败: {}", user.username, e),
+    }
+}
+
+/// 在令牌用途之外，追加由用户状态决定的待完成操作
+fn required_actions(user: &User, mut actions: Vec<RequiredAction>) -> Vec<RequiredAction> {
+    if user.must_change_password {
+        actions.push(RequiredAction::PasswordChange);
+    }
+    actions
+}
+
+/// 身份验证通过后签发令牌：已启用两步验证时返回验证挑战，
+/// 所在用户组要求但尚未启用时签发只能绑定 TOTP 的受限令牌。
+/// `mfa_verified` 表示本次登录方式已满足多因素认证（如通行密钥）。
+/// 签发可用令牌时以 `method` 记录登录审计事件，并清除 `login_name` 的失败计数；
+/// 仍需第二因素时保留计数，由 [`login_2fa`] 验证通过后清除。
+pub(crate) async fn complete_login(
+    state: &AppState,
+    meta: &RequestMeta,
+    user: User,
+    method: &str,
+    mfa_verified: bool,
+    login_name: Option<&str>,
+) -> Result<Response, AppError> {
+    let jwt = &state.config.jwt;
+
+    if !mfa_verified
+        && TotpService::find_enabled(&state.pool, user.id)
+            .await?
+            .is_some()
+    {
+        let challenge_id =
+            MfaChallengeService::create(&state.pool, user.id, MFA_CHALLENGE_EXPIRES_IN).await?;
+        let mfa_token = encode_mfa_challenge_token(
+            user.id,
+            challenge_id,
+            &state.jwt_keys,
+            MFA_CHALLENGE_EXPIRES_IN,
+        )?;
+
+        return Ok(Json(MfaChallengeResponse {
+            mfa_required: true,
+            mfa_token,
+        })
+        .into_response());
+    }
+
+    if let Some(login_name) = login_name {
+        reset_login_failures(state, login_name).await?;
+    }
+
+    if !mfa_verified && TotpService::is_required(&state.pool, user.id).await? {
+        let token = encode_token_with_purpose(
+            user.id,
+            Some(TokenPurpose::MfaEnrollment),
+            &state.jwt_keys,
+            MFA_ENROLLMENT_EXPIRES_IN,
+        )?;
+        record_login(state, meta, &user, method).await;
+
+        return Ok(Json(LoginResponse {
+            token,
+            required_actions: required_actions(&user, vec![RequiredAction::TwoFactorSetup]),
+            user,
+        })
+        .into_response());
+    }
+
+    let token = encode_token(user.id, &state.jwt_keys, jwt.expires_in)?;
+    record_login(state, meta, &user, method).await;
+
+    Ok(Json(LoginResponse {
+        token,
+        required_actions: required_actions(&user, Vec::new()),
+        user,
+    })
+    .into_response())
+}
+
+async fn reset_login_failures(state: &AppState, login_name: &str) -> Result<(), AppError> {
+    LoginThrottleService::reset(
+        &state.pool,
+        login_throttle::scope::USERNAME,
+        &LoginThrottleService::username_key(login_name),
+    )
+    .await?;
+    Ok(())
+}
+
+async fn record_login(state: &AppState, meta: &RequestMeta, user: &User, method: &str) {
+    let event = meta
+        .audit_event(audit::action::LOGIN)
+        .actor(user)
+        .diff(json!({ "method": method }));
+    AuditService::record(&state.pool, event).await;
+}
+
+pub async fn login_2fa(
+    State(state): State<AppState>,
+    meta: RequestMeta,
+    Json(payload): Json<TwoFactorLoginRequest>,
+) -> Result<Json<LoginResponse>, AppError> {
+    let claims = JwtKeyService::decode_token(
+        &state.pool,
+        &state.config.jwt,
+        &state.jwt_keys,
+        &payload.mfa_token,
+    )
+    .await
+    .ok()
+    .filter(|c| c.purpose == Some(TokenPurpose::MfaChallenge))
+    .ok_or_else(|| AppError::new(ErrorCode::InvalidToken, "auth.mfa_token_invalid"))?;
+    let challenge_id = claims
+        .jti
+        .ok_or_else(|| AppError::new(ErrorCode::InvalidToken, "auth.mfa_token_invalid"))?;
+
+    let user = UserService::find_by_id(&state.pool, claims.sub)
+        .await?
+        .filter(|u| u.status == UserStatus::Active as i16)
+        .ok_or_else(|| AppError::new(ErrorCode::InvalidToken, "auth.mfa_token_invalid"))?;
+
+    let totp = TotpService::find_enabled(&state.pool, user.id)
+        .await?
+        .ok_or_else(|| AppError::new(ErrorCode::InvalidToken, "auth.mfa_token_invalid"))?;
+
+    let method = match payload.recovery_code {
+        Some(_) => "password+recovery_code",
+        None => "password+totp",
+    };
+    let failed = |reason: &str| {
+        meta.audit_event(audit::action::LOGIN_FAILED)
+            .actor(&user)
+            .diff(json!({ "reason": reason, "method": method }))
+    };
+
+    // 挑战已使用或错误次数用尽后作废，需重新输入密码
+    if !MfaChallengeService::reserve_attempt(&state.pool, challenge_id, user.id).await? {
+        AuditService::record(&state.pool, failed("mfa_challenge_ended")).await;
+        return Err(AppError::new(
+            ErrorCode::InvalidToken,
+            "auth.mfa_challenge_ended",
         ));
     }
 
-    let token = encode_token(
-        user.id,
-        &state.config.jwt.secret,
-        state.config.jwt.expires_in,
+    if let Err(e) = verify_second_factor_throttled(
+        &state,
+        &meta,
+        &user.username,
+        &totp,
+        payload.code.as_deref(),
+        payload.recovery_code.as_deref(),
     )
-    .map_err(|e| {
-        tracing::error!("JWT 生成失败: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "error": "内部服务器错误" })),
-        )
-    })?;
+    .await
+    {
+        let reason = match e.code {
+            ErrorCode::RateLimited => "locked",
+            _ => "invalid_second_factor",
+        };
+        AuditService::record(&state.pool, failed(reason)).await;
+        return Err(e);
+    }
+
+    if !MfaChallengeService::consume(&state.pool, challenge_id).await? {
+        return Err(AppError::new(
+            ErrorCode::InvalidToken,
+            "auth.mfa_token_invalid",
+        ));
+    }
+    reset_login_failures(&state, &user.username).await?;
+
+    let token = encode_token(user.id, &state.jwt_keys, state.config.jwt.expires_in)?;
+    record_login(&state, &meta, &user, method).await;
 
-    Ok(Json(LoginResponse { token, user }))
+    Ok(Json(LoginResponse {
+        token,
+        required_actions: required_actions(&user, Vec::new()),
+        user,
+    }))
+}
+
+#[derive(Debug, Serialize)]
+pub struct MeResponse {
+    #[serde(flatten)]
+    pub user: User,
+    /// 管理员模拟登录时为实际操作的管理员
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub act: Option<Impersonator>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Impersonator {
+    pub id: Uuid,
+    pub username: String,
 }
 
-pub async fn me(AuthUser { user, .. }: AuthUser) -> Json<User> {
-    Json(user)
+pub async fn me(RestrictedAuthUser(auth): RestrictedAuthUser) -> Json<MeResponse> {
+    Json(MeResponse {
+        act: auth.impersonator.map(|actor| Impersonator {
+            id: actor.id,
+            username: actor.username,
+        }),
+        user: auth.user,
+    })
 }
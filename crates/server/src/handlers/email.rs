@@ -0,0 +1,155 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    error::{AppError, ErrorCode},
+    handlers::{forbid_impersonation, require_session},
+    i18n::{Locale, Message},
+    mail::Email,
+    middleware::{AppState, AuthUser, Json, RequestMeta},
+    models::User,
+    services::{AuditService, EmailVerificationService, UserService, audit, email_verification},
+};
+
+#[derive(Debug, Deserialize)]
+pub struct ChangeEmailRequest {
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConfirmEmailRequest {
+    pub token: String,
+}
+
+/// 邮箱是否已被其他用户使用
+async fn email_taken(state: &AppState, email: &str, user_id: uuid::Uuid) -> Result<bool, AppError> {
+    Ok(UserService::find_by_email(&state.pool, email)
+        .await?
+        .is_some_and(|u| u.id != user_id))
+}
+
+/// 生成验证令牌并在后台发送验证邮件
+pub(crate) async fn send_verification_email(
+    state: &AppState,
+    user: &User,
+    email: &str,
+) -> Result<(), AppError> {
+    let token = EmailVerificationService::create(&state.pool, user.id, email)
+        .await?
+        .ok_or_else(|| AppError::new(ErrorCode::RateLimited, "email.send_too_frequent"))?;
+
+    let link = format!(
+        "{}/verify-email?token={}",
+        state.config.server.public_url, token
+    );
+    let locale = Locale::for_user(user);
+    let message = Email {
+        to: email.to_string(),
+        subject: Message::new("email.verify.subject").render(locale),
+        body: Message::new("email.verify.body")
+            .arg("name", &user.nickname)
+            .arg("hours", email_verification::TOKEN_EXPIRES_IN / 3600)
+            .arg("email", email)
+            .arg("link", link)
+            .render(locale),
+    };
+
+    let mailer = state.mailer.clone();
+    tokio::spawn(async move {
+        if let Err(e) = mailer.send(&message).await {
+            tracing::error!("发送验证邮件失败: {}", e);
+        }
+    });
+
+    Ok(())
+}
+
+/// 修改邮箱：向新地址发送验证邮件，确认后才替换当前邮箱
+pub async fn change_email(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(payload): Json<ChangeEmailRequest>,
+) -> impl IntoResponse {
+    require_session(&auth)?;
+    forbid_impersonation(&auth)?;
+
+    let email = payload.email.trim();
+    if email.len() > 255 || email.parse::<lettre::Address>().is_err() {
+        return Err(AppError::validation("email", "email.invalid"));
+    }
+
+    if auth.user.email_verified_at.is_some()
+        && auth
+            .user
+            .email
+            .as_deref()
+            .is_some_and(|current| current.eq_ignore_ascii_case(email))
+    {
+        return Err(AppError::validation("email", "email.unchanged"));
+    }
+
+    if email_taken(&state, email, auth.user_id).await? {
+        return Err(AppError::conflict("email.taken"));
+    }
+
+    send_verification_email(&state, &auth.user, email).await?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// 重新发送当前邮箱的验证邮件
+pub async fn resend_verification(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> impl IntoResponse {
+    require_session(&auth)?;
+    forbid_impersonation(&auth)?;
+
+    let email = auth
+        .user
+        .email
+        .as_deref()
+        .ok_or_else(|| AppError::bad_request("email.not_set"))?;
+
+    if auth.user.email_verified_at.is_some() {
+        return Err(AppError::bad_request("email.already_verified"));
+    }
+
+    send_verification_email(&state, &auth.user, email).await?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// 通过邮件中的令牌确认邮箱
+pub async fn confirm_email(
+    State(state): State<AppState>,
+    meta: RequestMeta,
+    Json(payload): Json<ConfirmEmailRequest>,
+) -> impl IntoResponse {
+    let record = EmailVerificationService::consume(&state.pool, &payload.token)
+        .await?
+        .ok_or_else(|| AppError::validation("token", "email.verification_link_invalid"))?;
+
+    // 发送验证邮件后该地址可能已被其他用户确认
+    if email_taken(&state, &record.email, record.user_id).await? {
+        return Err(AppError::conflict("email.taken"));
+    }
+
+    let user = UserService::find_by_id(&state.pool, record.user_id)
+        .await?
+        .ok_or_else(|| AppError::validation("token", "email.verification_link_invalid"))?;
+
+    UserService::set_verified_email(&state.pool, record.user_id, &record.email).await?;
+
+    tracing::info!("用户 {} 验证了邮箱", record.user_id);
+
+    let event = meta
+        .audit_event(audit::action::EMAIL_CHANGE)
+        .actor(&user)
+        .target("user", user.id)
+        .diff(json!({ "old": user.email, "new": record.email }));
+    AuditService::record(&state.pool, event).await;
+
+    Ok::<_, AppError>(StatusCode::NO_CONTENT)
+}
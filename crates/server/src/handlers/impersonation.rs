@@ -0,0 +1,77 @@
+use axum::{extract::State, response::IntoResponse};
+use serde::Serialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, ErrorCode},
+    handlers::{forbid_impersonation, require_permission, require_session},
+    middleware::{AppState, AuthUser, Json, Path, RequestMeta},
+    models::{User, UserStatus},
+    services::{AuditService, PermissionService, UserService, audit},
+    utils::jwt::encode_impersonation_token,
+};
+
+const PERMISSION: &str = "admin.impersonate";
+
+/// 模拟登录令牌的最长有效期（秒）
+const IMPERSONATION_EXPIRES_IN: i64 = 3600;
+
+#[derive(Debug, Serialize)]
+pub struct ImpersonationResponse {
+    pub token: String,
+    pub user: User,
+    pub expires_in: i64,
+}
+
+/// 签发以目标用户身份访问的令牌，令牌中的 `act` 记录实际操作的管理员
+pub async fn impersonate_user(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    meta: RequestMeta,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    require_session(&auth)?;
+    forbid_impersonation(&auth)?;
+    require_permission(&state, &auth, PERMISSION).await?;
+
+    if id == auth.user_id {
+        return Err(AppError::bad_request("impersonation.self"));
+    }
+
+    let user = UserService::find_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| AppError::not_found("user.not_found"))?;
+
+    if user.status != UserStatus::Active as i16 {
+        return Err(AppError::bad_request("user.disabled"));
+    }
+
+    // 只能模拟权限不超过自己的用户，避免借此提升权限
+    let granted = PermissionService::list_user_permissions(&state.pool, auth.user_id).await?;
+    let required = PermissionService::list_user_permissions(&state.pool, user.id).await?;
+    if !required
+        .iter()
+        .all(|p| PermissionService::is_granted(&granted, p))
+    {
+        return Err(AppError::new(
+            ErrorCode::PermissionDenied,
+            "impersonation.privilege_escalation",
+        ));
+    }
+
+    let expires_in = IMPERSONATION_EXPIRES_IN.min(state.config.jwt.expires_in);
+    let token = encode_impersonation_token(user.id, auth.user_id, &state.jwt_keys, expires_in)?;
+
+    let event = auth
+        .audit_event(&meta, audit::action::IMPERSONATE)
+        .target("user", user.id)
+        .diff(json!({ "username": user.username, "expires_in": expires_in }));
+    AuditService::record(&state.pool, event).await;
+
+    Ok::<_, AppError>(Json(ImpersonationResponse {
+        token,
+        user,
+        expires_in,
+    }))
+}
@@ -0,0 +1,51 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use jsonwebtoken::jwk::JwkSet;
+use serde_json::json;
+
+use crate::{
+    config::JwtAlgorithm,
+    error::AppError,
+    handlers::require_permission,
+    middleware::{AppState, AuthUser, Json, RequestMeta},
+    services::{AuditService, JwtKeyService, audit},
+};
+
+const PERMISSION: &str = "admin.jwt_keys";
+
+/// 发布用于验证令牌的公钥，供其他服务离线校验
+pub async fn jwks(State(state): State<AppState>) -> Json<JwkSet> {
+    Json(state.jwt_keys.jwks())
+}
+
+pub async fn list_jwt_keys(State(state): State<AppState>, auth: AuthUser) -> impl IntoResponse {
+    require_permission(&state, &auth, PERMISSION).await?;
+
+    let keys = JwtKeyService::list(&state.pool).await?;
+
+    Ok::<_, AppError>(Json(keys))
+}
+
+/// 轮换签名密钥，旧密钥在其签发的令牌过期前仍可用于验证
+pub async fn rotate_jwt_key(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    meta: RequestMeta,
+) -> impl IntoResponse {
+    require_permission(&state, &auth, PERMISSION).await?;
+
+    if state.config.jwt.algorithm == JwtAlgorithm::Hs256 {
+        return Err(AppError::bad_request("jwt_key.rotation_unsupported"));
+    }
+
+    let key = JwtKeyService::rotate(&state.pool, &state.config.jwt).await?;
+    JwtKeyService::reload(&state.pool, &state.config.jwt, &state.jwt_keys).await?;
+
+    tracing::info!("用户 {} 轮换了 JWT 签名密钥", auth.user.username);
+    let event = auth
+        .audit_event(&meta, audit::action::JWT_KEY_ROTATE)
+        .target("jwt_key", &key.kid)
+        .diff(json!({ "algorithm": key.algorithm }));
+    AuditService::record(&state.pool, event).await;
+
+    Ok((StatusCode::CREATED, Json(key)))
+}
@@ -0,0 +1,45 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use serde::Deserialize;
+
+use crate::{
+    error::AppError,
+    handlers::forbid_impersonation,
+    i18n::{Locale, Message},
+    middleware::{AppState, AuthUser, Json},
+    services::UserService,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateLocaleRequest {
+    /// 为空时清除偏好，改为按 `Accept-Language` 选择
+    pub locale: Option<String>,
+}
+
+/// 设置当前用户的界面语言偏好，用于接口提示和邮件
+pub async fn update_locale(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(payload): Json<UpdateLocaleRequest>,
+) -> impl IntoResponse {
+    forbid_impersonation(&auth)?;
+
+    let locale = match payload.locale.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(tag) => {
+            let locale = Locale::parse(tag).ok_or_else(|| {
+                let supported: Vec<&str> = Locale::ALL.iter().map(|l| l.tag()).collect();
+                AppError::validation(
+                    "locale",
+                    Message::new("locale.unsupported")
+                        .arg("locale", tag)
+                        .arg("supported", supported.join(", ")),
+                )
+            })?;
+            Some(locale.tag())
+        }
+    };
+
+    UserService::set_locale(&state.pool, auth.user_id, locale).await?;
+
+    Ok::<_, AppError>(StatusCode::NO_CONTENT)
+}
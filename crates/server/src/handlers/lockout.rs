@@ -0,0 +1,78 @@
+use std::net::IpAddr;
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    handlers::require_permission,
+    middleware::{AppState, AuthUser, Path, RequestMeta},
+    services::{AuditService, LoginThrottleService, UserService, audit, login_throttle::scope},
+};
+
+const PERMISSION: &str = "admin.users";
+
+/// 解除用户因登录失败过多导致的锁定
+pub async fn unlock_user(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    meta: RequestMeta,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    require_permission(&state, &auth, PERMISSION).await?;
+
+    let user = UserService::find_by_id(&state.pool, id)
+        .await?
+        .ok_or_else(|| AppError::not_found("user.not_found"))?;
+
+    LoginThrottleService::reset(
+        &state.pool,
+        scope::USERNAME,
+        &LoginThrottleService::username_key(&user.username),
+    )
+    .await?;
+
+    tracing::info!(
+        "用户 {} 解除了 {} 的登录锁定",
+        auth.user.username,
+        user.username
+    );
+    AuditService::record(
+        &state.pool,
+        auth.audit_event(&meta, audit::action::UNLOCK)
+            .target("user", user.id),
+    )
+    .await;
+
+    Ok::<_, AppError>(StatusCode::NO_CONTENT)
+}
+
+/// 解除客户端 IP 的登录锁定
+pub async fn unlock_ip(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    meta: RequestMeta,
+    Path(ip): Path<String>,
+) -> impl IntoResponse {
+    require_permission(&state, &auth, PERMISSION).await?;
+
+    let ip: IpAddr = ip
+        .parse()
+        .map_err(|_| AppError::validation("ip", "lockout.invalid_ip"))?;
+
+    let unlocked = LoginThrottleService::reset(&state.pool, scope::IP, &ip.to_string()).await?;
+
+    if !unlocked {
+        return Err(AppError::not_found("lockout.ip_not_locked"));
+    }
+
+    tracing::info!("用户 {} 解除了 IP {} 的登录锁定", auth.user.username, ip);
+    AuditService::record(
+        &state.pool,
+        auth.audit_event(&meta, audit::action::IP_UNLOCK)
+            .target("ip", ip),
+    )
+    .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,3 +1,77 @@
+pub mod api_key;
+pub mod audit;
 pub mod auth;
+pub mod email;
+pub mod impersonation;
+pub mod jwt_key;
+pub mod locale;
+pub mod lockout;
+pub mod oidc;
+pub mod passkey;
+pub mod password;
+pub mod setting;
+pub mod two_factor;
 
-pub use auth::{login, me};
+use crate::{
+    error::{AppError, ErrorCode},
+    middleware::{AppState, AuthUser},
+};
+
+pub use api_key::{create_api_key, delete_api_key, list_api_keys};
+pub use audit::{export_audit_events, list_audit_events};
+pub use auth::{login, login_2fa, me};
+pub use email::{change_email, confirm_email, resend_verification};
+pub use impersonation::impersonate_user;
+pub use jwt_key::{jwks, list_jwt_keys, rotate_jwt_key};
+pub use locale::update_locale;
+pub use lockout::{unlock_ip, unlock_user};
+pub use oidc::{list_oidc_providers, oidc_authorize, oidc_callback};
+pub use passkey::{
+    delete_passkey, finish_passkey_login, finish_passkey_registration, list_passkeys,
+    start_passkey_login, start_passkey_registration,
+};
+pub use password::{change_password, forgot_password, reset_password};
+pub use setting::{delete_setting, list_settings, update_setting};
+pub use two_factor::{
+    disable_totp, enable_totp, regenerate_recovery_codes, setup_totp, two_factor_status,
+};
+
+pub(crate) async fn require_permission(
+    state: &AppState,
+    auth: &AuthUser,
+    permission: &str,
+) -> Result<(), AppError> {
+    if !auth.has_permission(&state.pool, permission).await? {
+        return Err(AppError::new(
+            ErrorCode::PermissionDenied,
+            "error.permission_denied",
+        ));
+    }
+    Ok(())
+}
+
+/// 账户安全设置和 API Key 管理只能通过登录会话操作，避免泄露的 API Key 被用来
+/// 自我续期或接管账户
+pub(crate) fn require_session(auth: &AuthUser) -> Result<(), AppError> {
+    if auth.api_key.is_some() {
+        return Err(AppError::forbidden("error.session_required"));
+    }
+    Ok(())
+}
+
+/// 模拟登录只用于查看用户所见内容，不允许修改密码、两步验证等账户安全设置
+pub(crate) fn forbid_impersonation(auth: &AuthUser) -> Result<(), AppError> {
+    if let Some(actor) = &auth.impersonator {
+        tracing::warn!(
+            target: "audit",
+            actor = %actor.username,
+            user = %auth.user.username,
+            "模拟登录期间尝试执行受限操作"
+        );
+        return Err(AppError::new(
+            ErrorCode::ImpersonationForbidden,
+            "error.impersonation_forbidden",
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use axum::{
+    extract::State,
+    response::{IntoResponse, Redirect},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::OidcProviderConfig,
+    error::{AppError, ErrorCode},
+    handlers::auth::complete_login,
+    middleware::{AppState, Json, Path, Query, RequestMeta},
+    models::UserStatus,
+    services::{IdentityService, OidcService},
+};
+
+#[derive(Debug, Serialize)]
+pub struct OidcProviderInfo {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OidcCallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+}
+
+fn find_provider<'a>(state: &'a AppState, name: &str) -> Result<&'a OidcProviderConfig, AppError> {
+    state
+        .config
+        .oidc_provider(name)
+        .ok_or_else(|| AppError::not_found("oidc.provider_not_found"))
+}
+
+pub async fn list_oidc_providers(State(state): State<AppState>) -> Json<Vec<OidcProviderInfo>> {
+    Json(
+        state
+            .config
+            .oidc
+            .iter()
+            .map(|p| OidcProviderInfo {
+                name: p.name.clone(),
+            })
+            .collect(),
+    )
+}
+
+pub async fn oidc_authorize(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+) -> impl IntoResponse {
+    let provider = find_provider(&state, &provider)?;
+
+    let url = OidcService::authorize(&state.pool, &state.http, provider)
+        .await
+        .map_err(|e| {
+            tracing::error!("创建 OIDC 授权请求失败: {}", e);
+            AppError::upstream("oidc.provider_unavailable")
+        })?;
+
+    Ok::<_, AppError>(Redirect::to(&url))
+}
+
+pub async fn oidc_callback(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+    Query(query): Query<OidcCallbackQuery>,
+    meta: RequestMeta,
+) -> impl IntoResponse {
+    let provider = find_provider(&state, &provider)?;
+
+    if let Some(e) = &query.error {
+        tracing::debug!("OIDC 授权被拒绝: {}", e);
+        return Err(AppError::unauthorized("oidc.access_denied"));
+    }
+
+    let (Some(code), Some(oidc_state)) = (&query.code, &query.state) else {
+        return Err(AppError::bad_request("oidc.missing_parameters"));
+    };
+
+    let pending = OidcService::take_state(&state.pool, &provider.name, oidc_state)
+        .await?
+        .ok_or_else(|| AppError::bad_request("oidc.state_invalid"))?;
+
+    let identity = OidcService::exchange(&state.http, provider, &pending, code)
+        .await
+        .map_err(|e| {
+            tracing::warn!("OIDC 令牌交换失败: {}", e);
+            AppError::unauthorized("oidc.verification_failed")
+        })?;
+
+    let user = IdentityService::resolve_user(
+        &state.pool,
+        &provider.name,
+        &provider.group_mapping,
+        &identity,
+    )
+    .await
+    .map_err(|e| AppError::internal(format!("OIDC 用户关联失败: {:#}", e)))?;
+
+    if user.status != UserStatus::Active as i16 {
+        return Err(AppError::new(ErrorCode::AccountDisabled, "user.disabled"));
+    }
+
+    complete_login(
+        &state,
+        &meta,
+        user,
+        &format!("oidc:{}", provider.name),
+        false,
+        None,
+    )
+    .await
+}
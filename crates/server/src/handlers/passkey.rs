@@ -0,0 +1,250 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+use webauthn_rs::prelude::{
+    CreationChallengeResponse, DiscoverableAuthentication, DiscoverableKey,
+    Passkey as WebauthnPasskey, PasskeyRegistration, PublicKeyCredential,
+    RegisterPublicKeyCredential, RequestChallengeResponse,
+};
+use webauthn_rs_proto::ResidentKeyRequirement;
+
+use crate::{
+    error::{AppError, ErrorCode},
+    handlers::{auth::complete_login, forbid_impersonation, require_session},
+    middleware::{AppState, AuthUser, Json, Path, RequestMeta},
+    models::{Passkey, UserStatus, WebauthnChallenge},
+    services::{AuditService, PasskeyService, UserService, audit, passkey::challenge_kind},
+};
+
+#[derive(Debug, Serialize)]
+pub struct PasskeyRegistrationStart {
+    pub challenge_id: Uuid,
+    pub options: CreationChallengeResponse,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PasskeyRegistrationFinish {
+    pub challenge_id: Uuid,
+    pub name: String,
+    pub credential: RegisterPublicKeyCredential,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PasskeyLoginStart {
+    pub challenge_id: Uuid,
+    pub options: RequestChallengeResponse,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PasskeyLoginFinish {
+    pub challenge_id: Uuid,
+    pub credential: PublicKeyCredential,
+}
+
+fn ceremony_failed(e: impl std::fmt::Display) -> AppError {
+    tracing::debug!("WebAuthn 验证失败: {}", e);
+    AppError::new(ErrorCode::InvalidCredentials, "passkey.verification_failed")
+}
+
+fn to_webauthn_passkey(passkey: &Passkey) -> Result<WebauthnPasskey, AppError> {
+    serde_json::from_value(passkey.credential.clone()).map_err(AppError::internal)
+}
+
+pub async fn list_passkeys(State(state): State<AppState>, auth: AuthUser) -> impl IntoResponse {
+    let passkeys = PasskeyService::list_by_user(&state.pool, auth.user_id).await?;
+
+    Ok::<_, AppError>(Json(passkeys))
+}
+
+pub async fn delete_passkey(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    meta: RequestMeta,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    require_session(&auth)?;
+    forbid_impersonation(&auth)?;
+
+    let deleted = PasskeyService::delete(&state.pool, auth.user_id, id).await?;
+
+    if !deleted {
+        return Err(AppError::not_found("passkey.not_found"));
+    }
+
+    let event = auth
+        .audit_event(&meta, audit::action::PASSKEY_DELETE)
+        .target("passkey", id);
+    AuditService::record(&state.pool, event).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn start_passkey_registration(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> impl IntoResponse {
+    require_session(&auth)?;
+    forbid_impersonation(&auth)?;
+
+    let webauthn = PasskeyService::webauthn(&state.config.webauthn)?;
+    let existing = PasskeyService::list_by_user(&state.pool, auth.user_id)
+        .await?
+        .iter()
+        .map(|p| to_webauthn_passkey(p).map(|p| p.cred_id().clone()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (mut options, registration) = webauthn
+        .start_passkey_registration(
+            auth.user_id,
+            &auth.user.username,
+            &auth.user.nickname,
+            Some(existing),
+        )
+        .map_err(AppError::internal)?;
+    // 登录只使用可发现凭证，要求认证器在本地保存凭证
+    if let Some(selection) = options.public_key.authenticator_selection.as_mut() {
+        selection.resident_key = Some(ResidentKeyRequirement::Required);
+        selection.require_resident_key = true;
+    }
+
+    let challenge = WebauthnChallenge::new(
+        Some(auth.user_id),
+        challenge_kind::REGISTER.to_string(),
+        serde_json::to_value(&registration).map_err(AppError::internal)?,
+    );
+    PasskeyService::save_challenge(&state.pool, &challenge).await?;
+
+    Ok::<_, AppError>(Json(PasskeyRegistrationStart {
+        challenge_id: challenge.id,
+        options,
+    }))
+}
+
+pub async fn finish_passkey_registration(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    meta: RequestMeta,
+    Json(payload): Json<PasskeyRegistrationFinish>,
+) -> impl IntoResponse {
+    require_session(&auth)?;
+    forbid_impersonation(&auth)?;
+
+    let name = payload.name.trim();
+    if name.is_empty() || name.chars().count() > 100 {
+        return Err(AppError::validation("name", "error.invalid_name"));
+    }
+
+    let challenge = PasskeyService::take_challenge(&state.pool, payload.challenge_id)
+        .await?
+        .filter(|c| c.kind == challenge_kind::REGISTER && c.user_id == Some(auth.user_id))
+        .ok_or_else(|| AppError::bad_request("passkey.registration_invalid"))?;
+    let registration: PasskeyRegistration =
+        serde_json::from_value(challenge.state).map_err(AppError::internal)?;
+
+    let webauthn = PasskeyService::webauthn(&state.config.webauthn)?;
+    let credential = webauthn
+        .finish_passkey_registration(&payload.credential, &registration)
+        .map_err(|e| {
+            tracing::debug!("通行密钥注册失败: {}", e);
+            AppError::bad_request("passkey.registration_failed")
+        })?;
+
+    let credential_id = credential.cred_id().to_vec();
+    if PasskeyService::find_by_credential_id(&state.pool, &credential_id)
+        .await?
+        .is_some()
+    {
+        return Err(AppError::conflict("passkey.already_registered"));
+    }
+
+    let passkey = Passkey::new(
+        auth.user_id,
+        name.to_string(),
+        credential_id,
+        serde_json::to_value(&credential).map_err(AppError::internal)?,
+    );
+    let passkey = PasskeyService::create(&state.pool, &passkey).await?;
+
+    let event = auth
+        .audit_event(&meta, audit::action::PASSKEY_CREATE)
+        .target("passkey", passkey.id)
+        .diff(json!({ "name": passkey.name }));
+    AuditService::record(&state.pool, event).await;
+
+    Ok((StatusCode::CREATED, Json(passkey)))
+}
+
+/// 始终使用可发现凭证流程，不接受用户名，
+/// 响应中不包含任何用户的凭证 ID，无法据此判断用户名是否存在
+pub async fn start_passkey_login(State(state): State<AppState>) -> impl IntoResponse {
+    let webauthn = PasskeyService::webauthn(&state.config.webauthn)?;
+
+    let (options, authentication) = webauthn
+        .start_discoverable_authentication()
+        .map_err(AppError::internal)?;
+    let challenge = WebauthnChallenge::new(
+        None,
+        challenge_kind::DISCOVERABLE.to_string(),
+        serde_json::to_value(&authentication).map_err(AppError::internal)?,
+    );
+    PasskeyService::save_challenge(&state.pool, &challenge).await?;
+
+    Ok::<_, AppError>(Json(PasskeyLoginStart {
+        challenge_id: challenge.id,
+        options,
+    }))
+}
+
+pub async fn finish_passkey_login(
+    State(state): State<AppState>,
+    meta: RequestMeta,
+    Json(payload): Json<PasskeyLoginFinish>,
+) -> impl IntoResponse {
+    let challenge = PasskeyService::take_challenge(&state.pool, payload.challenge_id)
+        .await?
+        .ok_or_else(|| AppError::bad_request("passkey.login_invalid"))?;
+
+    let webauthn = PasskeyService::webauthn(&state.config.webauthn)?;
+    let credential_id = payload.credential.get_credential_id().to_vec();
+    let mut passkey = PasskeyService::find_by_credential_id(&state.pool, &credential_id)
+        .await?
+        .ok_or_else(|| ceremony_failed("未知的凭证"))?;
+    let mut credential = to_webauthn_passkey(&passkey)?;
+
+    if challenge.kind != challenge_kind::DISCOVERABLE {
+        return Err(AppError::bad_request("passkey.login_invalid"));
+    }
+
+    let (user_id, _) = webauthn
+        .identify_discoverable_authentication(&payload.credential)
+        .map_err(ceremony_failed)?;
+    if user_id != passkey.user_id {
+        return Err(ceremony_failed("凭证与用户不匹配"));
+    }
+    let authentication: DiscoverableAuthentication =
+        serde_json::from_value(challenge.state).map_err(AppError::internal)?;
+    let result = webauthn
+        .finish_discoverable_authentication(
+            &payload.credential,
+            authentication,
+            &[DiscoverableKey::from(&credential)],
+        )
+        .map_err(ceremony_failed)?;
+
+    if credential.update_credential(&result).is_some() {
+        passkey.credential = serde_json::to_value(&credential).map_err(AppError::internal)?;
+    }
+    PasskeyService::record_use(&state.pool, &passkey).await?;
+
+    let user = UserService::find_by_id(&state.pool, passkey.user_id)
+        .await?
+        .ok_or_else(|| ceremony_failed("用户不存在"))?;
+
+    if user.status != UserStatus::Active as i16 {
+        return Err(AppError::new(ErrorCode::AccountDisabled, "user.disabled"));
+    }
+
+    // 通行密钥要求用户验证（生物识别或 PIN），本身即满足多因素认证
+    complete_login(&state, &meta, user, "passkey", true, None).await
+}
@@ -0,0 +1,187 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use serde::Deserialize;
+
+use crate::{
+    error::AppError,
+    handlers::{auth::LoginResponse, forbid_impersonation, require_session},
+    i18n::{Locale, Message},
+    mail::Email,
+    middleware::{AppState, Json, RequestMeta, RequiredAction, RestrictedAuthUser},
+    models::{User, UserStatus},
+    services::{
+        AuditService, LoginThrottleService, PasswordPolicy, PasswordResetService, UserService,
+        audit, login_throttle, password_reset,
+    },
+    utils::{hash_password, jwt::encode_token, verify_password},
+};
+
+#[derive(Debug, Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub new_password: String,
+}
+
+async fn validate_new_password(
+    state: &AppState,
+    password: &str,
+    user: &User,
+) -> Result<(), AppError> {
+    PasswordPolicy::load(&state.pool)
+        .await?
+        .validate(password, &user.username)
+        .map_err(|msg| AppError::validation("new_password", msg))
+}
+
+/// 修改当前用户的密码，被要求修改密码的受限令牌也可调用。
+/// 修改后此前签发的令牌全部失效，响应中返回新的令牌
+pub async fn change_password(
+    State(state): State<AppState>,
+    RestrictedAuthUser(auth): RestrictedAuthUser,
+    meta: RequestMeta,
+    Json(payload): Json<ChangePasswordRequest>,
+) -> Result<Json<LoginResponse>, AppError> {
+    require_session(&auth)?;
+    forbid_impersonation(&auth)?;
+
+    if matches!(auth.required_action, Some(action) if action != RequiredAction::PasswordChange) {
+        return Err(AppError::forbidden("auth.other_action_required"));
+    }
+
+    let password_hash = auth
+        .user
+        .password_hash
+        .as_deref()
+        .ok_or_else(|| AppError::bad_request("password.not_set"))?;
+
+    if !verify_password(&payload.current_password, password_hash)? {
+        return Err(AppError::validation(
+            "current_password",
+            "password.incorrect",
+        ));
+    }
+
+    if payload.new_password == payload.current_password {
+        return Err(AppError::validation(
+            "new_password",
+            "password.same_as_current",
+        ));
+    }
+
+    validate_new_password(&state, &payload.new_password, &auth.user).await?;
+
+    let new_hash = hash_password(&payload.new_password)?;
+    UserService::update_password(&state.pool, auth.user_id, &new_hash).await?;
+
+    tracing::info!("用户 {} 修改了密码", auth.user.username);
+    AuditService::record(
+        &state.pool,
+        auth.audit_event(&meta, audit::action::PASSWORD_CHANGE),
+    )
+    .await;
+
+    let user = UserService::find_by_id(&state.pool, auth.user_id)
+        .await?
+        .ok_or_else(|| AppError::unauthorized("user.not_found"))?;
+    let token = encode_token(user.id, &state.jwt_keys, state.config.jwt.expires_in)?;
+
+    Ok(Json(LoginResponse {
+        token,
+        user,
+        required_actions: Vec::new(),
+    }))
+}
+
+/// 发送重置密码邮件；无论邮箱是否存在都返回相同结果，避免泄露注册信息
+pub async fn forgot_password(
+    State(state): State<AppState>,
+    Json(payload): Json<ForgotPasswordRequest>,
+) -> impl IntoResponse {
+    let user = UserService::find_by_email(&state.pool, payload.email.trim())
+        .await?
+        .filter(|u| u.status == UserStatus::Active as i16);
+
+    if let Some(user) = user
+        && let Some(email) = user.email.clone()
+        && let Some(token) = PasswordResetService::create(&state.pool, user.id).await?
+    {
+        let link = format!(
+            "{}/reset-password?token={}",
+            state.config.server.public_url, token
+        );
+        let locale = Locale::for_user(&user);
+        let message = Email {
+            to: email,
+            subject: Message::new("email.password_reset.subject").render(locale),
+            body: Message::new("email.password_reset.body")
+                .arg("name", &user.nickname)
+                .arg("minutes", password_reset::TOKEN_EXPIRES_IN / 60)
+                .arg("link", link)
+                .render(locale),
+        };
+
+        // 在后台发送，使响应时间不因邮箱是否存在而不同
+        let mailer = state.mailer.clone();
+        tokio::spawn(async move {
+            if let Err(e) = mailer.send(&message).await {
+                tracing::error!("发送重置密码邮件失败: {}", e);
+            }
+        });
+    }
+
+    Ok::<_, AppError>(StatusCode::ACCEPTED)
+}
+
+/// 使用邮件中的令牌设置新密码，成功后此前签发的令牌全部失效
+pub async fn reset_password(
+    State(state): State<AppState>,
+    meta: RequestMeta,
+    Json(payload): Json<ResetPasswordRequest>,
+) -> impl IntoResponse {
+    let invalid = || AppError::validation("token", "password.reset_link_invalid");
+
+    let record = PasswordResetService::find_valid(&state.pool, &payload.token)
+        .await?
+        .ok_or_else(invalid)?;
+
+    let user = UserService::find_by_id(&state.pool, record.user_id)
+        .await?
+        .filter(|u| u.status == UserStatus::Active as i16)
+        .ok_or_else(invalid)?;
+
+    validate_new_password(&state, &payload.new_password, &user).await?;
+
+    if !PasswordResetService::consume(&state.pool, &record).await? {
+        return Err(invalid());
+    }
+
+    let new_hash = hash_password(&payload.new_password)?;
+    UserService::update_password(&state.pool, user.id, &new_hash).await?;
+
+    // 通过邮箱验证身份后同时解除登录锁定
+    LoginThrottleService::reset(
+        &state.pool,
+        login_throttle::scope::USERNAME,
+        &LoginThrottleService::username_key(&user.username),
+    )
+    .await?;
+
+    tracing::info!("用户 {} 通过邮件重置了密码", user.username);
+    AuditService::record(
+        &state.pool,
+        meta.audit_event(audit::action::PASSWORD_RESET).actor(&user),
+    )
+    .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
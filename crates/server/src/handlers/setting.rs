@@ -0,0 +1,88 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use serde::Deserialize;
+use serde_json::{Value as JsonValue, json};
+
+use crate::{
+    error::AppError,
+    handlers::require_permission,
+    middleware::{AppState, AuthUser, Json, Path, RequestMeta},
+    models::SettingType,
+    services::{AuditService, SettingService, audit},
+};
+
+const PERMISSION: &str = "admin.settings";
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateSettingRequest {
+    pub value: JsonValue,
+    pub description: Option<String>,
+}
+
+pub async fn list_settings(State(state): State<AppState>, auth: AuthUser) -> impl IntoResponse {
+    require_permission(&state, &auth, PERMISSION).await?;
+
+    let settings = SettingService::list(&state.pool).await?;
+
+    Ok::<_, AppError>(Json(settings))
+}
+
+pub async fn update_setting(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    meta: RequestMeta,
+    Path(key): Path<String>,
+    Json(payload): Json<UpdateSettingRequest>,
+) -> impl IntoResponse {
+    require_permission(&state, &auth, PERMISSION).await?;
+
+    if key.is_empty() || key.len() > 100 {
+        return Err(AppError::validation("key", "setting.invalid_key"));
+    }
+
+    let previous = SettingService::find(&state.pool, &key).await?;
+
+    let setting = SettingService::set(
+        &state.pool,
+        &key,
+        &payload.value,
+        SettingType::of(&payload.value),
+        payload.description.as_deref(),
+    )
+    .await?;
+
+    let event = auth
+        .audit_event(&meta, audit::action::SETTING_UPDATE)
+        .target("setting", &key)
+        .diff(json!({
+            "old": previous.map(|p| p.value),
+            "new": setting.value,
+        }));
+    AuditService::record(&state.pool, event).await;
+
+    Ok(Json(setting))
+}
+
+pub async fn delete_setting(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    meta: RequestMeta,
+    Path(key): Path<String>,
+) -> impl IntoResponse {
+    require_permission(&state, &auth, PERMISSION).await?;
+
+    let previous = SettingService::find(&state.pool, &key).await?;
+
+    let deleted = SettingService::delete(&state.pool, &key).await?;
+
+    if !deleted {
+        return Err(AppError::not_found("setting.not_found"));
+    }
+
+    let event = auth
+        .audit_event(&meta, audit::action::SETTING_DELETE)
+        .target("setting", &key)
+        .diff(json!({ "old": previous.map(|p| p.value) }));
+    AuditService::record(&state.pool, event).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
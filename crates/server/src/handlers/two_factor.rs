@@ -0,0 +1,262 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{AppError, ErrorCode},
+    handlers::{forbid_impersonation, require_session},
+    middleware::{AppState, AuthUser, Json, RequestMeta, RestrictedAuthUser},
+    models::UserTotp,
+    services::{
+        AuditService, LoginThrottleService, TotpService, audit,
+        login_throttle::{LockoutPolicy, Reservation},
+    },
+    utils::jwt::encode_token,
+};
+
+#[derive(Debug, Serialize)]
+pub struct TwoFactorStatus {
+    pub enabled: bool,
+    pub required: bool,
+    pub recovery_codes_remaining: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TotpSetupResponse {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TotpEnableRequest {
+    pub code: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TotpEnableResponse {
+    pub recovery_codes: Vec<String>,
+    /// 新的完整令牌，替换绑定前签发的受限令牌
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TwoFactorCodeRequest {
+    pub code: Option<String>,
+    pub recovery_code: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
+
+/// 失败次数过多时返回的错误，`retry_after` 为需等待的秒数
+pub(crate) fn too_many_attempts(retry_after: i64) -> AppError {
+    AppError::new(ErrorCode::RateLimited, "auth.too_many_attempts")
+        .with_extra("retry_after", retry_after)
+}
+
+/// 校验第二因素，与密码登录共用按用户名和 IP 的失败计数，
+/// 避免持有会话或密码的人无限次猜测验证码
+pub(crate) async fn verify_second_factor_throttled(
+    state: &AppState,
+    meta: &RequestMeta,
+    username: &str,
+    totp: &UserTotp,
+    code: Option<&str>,
+    recovery_code: Option<&str>,
+) -> Result<(), AppError> {
+    let policy = LockoutPolicy::load(&state.pool).await?;
+    let attempt =
+        match LoginThrottleService::reserve(&state.pool, &policy, username, meta.ip).await? {
+            Reservation::Granted(attempt) => attempt,
+            Reservation::Locked(retry_after) => return Err(too_many_attempts(retry_after)),
+        };
+
+    match verify_second_factor(state, totp, code, recovery_code).await {
+        Err(e) if e.code == ErrorCode::InvalidMfaCode => {
+            attempt.failed();
+            Err(e)
+        }
+        result => {
+            LoginThrottleService::release(&state.pool, attempt).await?;
+            result
+        }
+    }
+}
+
+/// 校验 TOTP 验证码或恢复码
+pub(crate) async fn verify_second_factor(
+    state: &AppState,
+    totp: &UserTotp,
+    code: Option<&str>,
+    recovery_code: Option<&str>,
+) -> Result<(), AppError> {
+    let is_valid = match (code, recovery_code) {
+        (Some(code), _) => TotpService::verify_code(&state.pool, totp, code).await,
+        (None, Some(recovery_code)) => {
+            TotpService::use_recovery_code(&state.pool, totp.user_id, recovery_code).await
+        }
+        (None, None) => return Err(AppError::bad_request("two_factor.code_required")),
+    }?;
+
+    if !is_valid {
+        return Err(AppError::new(
+            ErrorCode::InvalidMfaCode,
+            "two_factor.invalid_code",
+        ));
+    }
+
+    Ok(())
+}
+
+async fn find_enabled_totp(state: &AppState, auth: &AuthUser) -> Result<UserTotp, AppError> {
+    TotpService::find_enabled(&state.pool, auth.user_id)
+        .await?
+        .ok_or_else(|| AppError::bad_request("two_factor.not_enabled"))
+}
+
+pub async fn two_factor_status(
+    State(state): State<AppState>,
+    RestrictedAuthUser(auth): RestrictedAuthUser,
+) -> impl IntoResponse {
+    let enabled = TotpService::find_enabled(&state.pool, auth.user_id)
+        .await?
+        .is_some();
+    let required = TotpService::is_required(&state.pool, auth.user_id).await?;
+    let recovery_codes_remaining =
+        TotpService::count_recovery_codes(&state.pool, auth.user_id).await?;
+
+    Ok::<_, AppError>(Json(TwoFactorStatus {
+        enabled,
+        required,
+        recovery_codes_remaining,
+    }))
+}
+
+pub async fn setup_totp(
+    State(state): State<AppState>,
+    RestrictedAuthUser(auth): RestrictedAuthUser,
+) -> impl IntoResponse {
+    require_session(&auth)?;
+    forbid_impersonation(&auth)?;
+
+    if TotpService::find_enabled(&state.pool, auth.user_id)
+        .await?
+        .is_some()
+    {
+        return Err(AppError::conflict("two_factor.already_enabled"));
+    }
+
+    let (secret, otpauth_uri) = TotpService::begin_enrollment(&state.pool, &auth.user).await?;
+
+    Ok(Json(TotpSetupResponse {
+        secret,
+        otpauth_uri,
+    }))
+}
+
+pub async fn enable_totp(
+    State(state): State<AppState>,
+    RestrictedAuthUser(auth): RestrictedAuthUser,
+    meta: RequestMeta,
+    Json(payload): Json<TotpEnableRequest>,
+) -> impl IntoResponse {
+    require_session(&auth)?;
+    forbid_impersonation(&auth)?;
+
+    let totp = TotpService::find(&state.pool, auth.user_id)
+        .await?
+        .ok_or_else(|| AppError::bad_request("two_factor.setup_required"))?;
+
+    if totp.is_enabled() {
+        return Err(AppError::conflict("two_factor.already_enabled"));
+    }
+
+    if !TotpService::verify_code(&state.pool, &totp, &payload.code).await? {
+        return Err(AppError::new(
+            ErrorCode::InvalidMfaCode,
+            "two_factor.invalid_code",
+        ));
+    }
+
+    TotpService::enable(&state.pool, auth.user_id).await?;
+    let recovery_codes = TotpService::regenerate_recovery_codes(&state.pool, auth.user_id).await?;
+    AuditService::record(
+        &state.pool,
+        auth.audit_event(&meta, audit::action::TOTP_ENABLE),
+    )
+    .await;
+
+    let token = encode_token(auth.user_id, &state.jwt_keys, state.config.jwt.expires_in)?;
+
+    Ok(Json(TotpEnableResponse {
+        recovery_codes,
+        token,
+    }))
+}
+
+pub async fn disable_totp(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    meta: RequestMeta,
+    Json(payload): Json<TwoFactorCodeRequest>,
+) -> impl IntoResponse {
+    require_session(&auth)?;
+    forbid_impersonation(&auth)?;
+
+    let totp = find_enabled_totp(&state, &auth).await?;
+
+    if TotpService::is_required(&state.pool, auth.user_id).await? {
+        return Err(AppError::forbidden("two_factor.required_by_group"));
+    }
+
+    verify_second_factor_throttled(
+        &state,
+        &meta,
+        &auth.user.username,
+        &totp,
+        payload.code.as_deref(),
+        payload.recovery_code.as_deref(),
+    )
+    .await?;
+
+    TotpService::disable(&state.pool, auth.user_id).await?;
+    AuditService::record(
+        &state.pool,
+        auth.audit_event(&meta, audit::action::TOTP_DISABLE),
+    )
+    .await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn regenerate_recovery_codes(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    meta: RequestMeta,
+    Json(payload): Json<TwoFactorCodeRequest>,
+) -> Result<Json<RecoveryCodesResponse>, AppError> {
+    require_session(&auth)?;
+    forbid_impersonation(&auth)?;
+
+    let totp = find_enabled_totp(&state, &auth).await?;
+
+    verify_second_factor_throttled(
+        &state,
+        &meta,
+        &auth.user.username,
+        &totp,
+        payload.code.as_deref(),
+        payload.recovery_code.as_deref(),
+    )
+    .await?;
+
+    let recovery_codes = TotpService::regenerate_recovery_codes(&state.pool, auth.user_id).await?;
+    AuditService::record(
+        &state.pool,
+        auth.audit_event(&meta, audit::action::RECOVERY_CODES_REGENERATE),
+    )
+    .await;
+
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
@@ -0,0 +1,187 @@
+use std::{collections::HashMap, sync::LazyLock};
+
+use crate::{middleware::locale, models::User};
+
+/// 支持的界面语言，默认简体中文
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    ZhCn,
+    EnUs,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::ZhCn, Locale::EnUs];
+
+    pub fn tag(self) -> &'static str {
+        match self {
+            Self::ZhCn => "zh-CN",
+            Self::EnUs => "en-US",
+        }
+    }
+
+    /// 按主语言匹配语言标签，如 `zh`、`zh-Hans`、`en-GB`
+    pub fn parse(tag: &str) -> Option<Self> {
+        let primary = tag.trim().split(['-', '_']).next()?;
+        if primary.eq_ignore_ascii_case("zh") {
+            Some(Self::ZhCn)
+        } else if primary.eq_ignore_ascii_case("en") {
+            Some(Self::EnUs)
+        } else {
+            None
+        }
+    }
+
+    /// 按 `Accept-Language` 中的权重选择第一个支持的语言
+    pub fn negotiate(accept_language: &str) -> Option<Self> {
+        let mut candidates: Vec<(f32, Self)> = accept_language
+            .split(',')
+            .filter_map(|item| {
+                let mut parts = item.split(';');
+                let locale = Self::parse(parts.next()?)?;
+                let quality = parts
+                    .find_map(|p| p.trim().strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+                (quality > 0.0).then_some((quality, locale))
+            })
+            .collect();
+        // 稳定排序，权重相同时保持原有顺序
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+        candidates.first().map(|&(_, locale)| locale)
+    }
+
+    /// 当前请求使用的语言
+    pub fn current() -> Self {
+        locale::current()
+    }
+
+    /// 发给用户的邮件等内容优先使用其语言偏好
+    pub fn for_user(user: &User) -> Self {
+        user.locale
+            .as_deref()
+            .and_then(Self::parse)
+            .unwrap_or_else(Self::current)
+    }
+
+    fn catalog(self) -> &'static HashMap<String, String> {
+        static ZH_CN: LazyLock<HashMap<String, String>> =
+            LazyLock::new(|| load(Locale::ZhCn, include_str!("../../locales/zh-CN.json")));
+        static EN_US: LazyLock<HashMap<String, String>> =
+            LazyLock::new(|| load(Locale::EnUs, include_str!("../../locales/en-US.json")));
+
+        match self {
+            Self::ZhCn => &ZH_CN,
+            Self::EnUs => &EN_US,
+        }
+    }
+}
+
+fn load(locale: Locale, source: &str) -> HashMap<String, String> {
+    let catalog: HashMap<String, String> = serde_json::from_str(source)
+        .unwrap_or_else(|e| panic!("语言包 {} 格式错误: {}", locale.tag(), e));
+
+    if locale != Locale::default() {
+        for key in Locale::default().catalog().keys() {
+            if !catalog.contains_key(key) {
+                tracing::warn!("语言包 {} 缺少消息: {}", locale.tag(), key);
+            }
+        }
+    }
+
+    catalog
+}
+
+/// 面向用户的消息，渲染时才按语言从语言包中取出文本
+///
+/// 文本中的 `{name}` 会被替换为同名参数
+#[derive(Debug, Clone)]
+pub struct Message {
+    key: &'static str,
+    args: Vec<(&'static str, String)>,
+}
+
+impl Message {
+    pub fn new(key: &'static str) -> Self {
+        Self {
+            key,
+            args: Vec::new(),
+        }
+    }
+
+    pub fn arg(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.args.push((name, value.to_string()));
+        self
+    }
+
+    pub fn key(&self) -> &'static str {
+        self.key
+    }
+
+    /// 缺少翻译时依次回退到默认语言和消息键本身
+    pub fn render(&self, locale: Locale) -> String {
+        let template = locale
+            .catalog()
+            .get(self.key)
+            .or_else(|| Locale::default().catalog().get(self.key))
+            .map_or(self.key, String::as_str);
+
+        self.args
+            .iter()
+            .fold(template.to_string(), |text, (name, value)| {
+                text.replace(&format!("{{{}}}", name), value)
+            })
+    }
+}
+
+impl From<&'static str> for Message {
+    fn from(key: &'static str) -> Self {
+        Self::new(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_highest_quality_supported_locale() {
+        assert_eq!(
+            Locale::negotiate("fr-FR, en-US;q=0.8, zh-CN;q=0.9"),
+            Some(Locale::ZhCn)
+        );
+        assert_eq!(
+            Locale::negotiate("zh;q=0.5, en-GB;q=0.7"),
+            Some(Locale::EnUs)
+        );
+    }
+
+    #[test]
+    fn keeps_header_order_for_equal_quality() {
+        assert_eq!(Locale::negotiate("en, zh"), Some(Locale::EnUs));
+        assert_eq!(Locale::negotiate("zh-TW, en"), Some(Locale::ZhCn));
+    }
+
+    #[test]
+    fn zero_quality_excludes_locale() {
+        assert_eq!(Locale::negotiate("zh;q=0, en;q=0.1"), Some(Locale::EnUs));
+        assert_eq!(Locale::negotiate("en;q=0"), None);
+    }
+
+    #[test]
+    fn ignores_unknown_and_malformed_tags() {
+        assert_eq!(Locale::negotiate("fr, de;q=0.9"), None);
+        assert_eq!(Locale::negotiate("*"), None);
+        assert_eq!(Locale::negotiate("en;q=abc, zh;q=0.2"), Some(Locale::ZhCn));
+        assert_eq!(Locale::negotiate(""), None);
+    }
+
+    #[test]
+    fn renders_with_fallback() {
+        let message = Message::new("password.too_short").arg("min", 8);
+        assert!(message.render(Locale::EnUs).contains('8'));
+        assert_eq!(
+            Message::new("no.such.key").render(Locale::EnUs),
+            "no.such.key"
+        );
+    }
+}
@@ -1,6 +1,9 @@
 pub mod config;
 pub mod database;
+pub mod error;
 pub mod handlers;
+pub mod i18n;
+pub mod mail;
 pub mod middleware;
 pub mod models;
 pub mod routes;
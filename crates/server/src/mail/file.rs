@@ -0,0 +1,52 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    config::MailConfig,
+    mail::{Email, Mailer},
+};
+
+/// 将每封邮件写入目录下的单独文件，便于开发和测试时查看
+#[derive(Debug)]
+pub struct FileMailer {
+    dir: PathBuf,
+    from: String,
+}
+
+impl FileMailer {
+    pub fn new(config: &MailConfig) -> Self {
+        Self {
+            dir: PathBuf::from(&config.file_dir),
+            from: config.from.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, email: &Email) -> anyhow::Result<()> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+
+        let now = Utc::now();
+        let path = self.dir.join(format!(
+            "{}-{}.eml",
+            now.format("%Y%m%d%H%M%S"),
+            Uuid::new_v4()
+        ));
+        let content = format!(
+            "From: {}\nTo: {}\nDate: {}\nSubject: {}\n\n{}\n",
+            self.from,
+            email.to,
+            now.to_rfc2822(),
+            email.subject,
+            email.body
+        );
+
+        tokio::fs::write(&path, content).await?;
+        tracing::debug!("邮件已写入 {}", path.display());
+        Ok(())
+    }
+}
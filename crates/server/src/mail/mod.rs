@@ -0,0 +1,44 @@
+mod file;
+mod smtp;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::config::{MailConfig, MailTransport};
+
+pub use file::FileMailer;
+pub use smtp::SmtpMailer;
+
+/// 纯文本邮件
+#[derive(Debug, Clone)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[async_trait]
+pub trait Mailer: std::fmt::Debug + Send + Sync {
+    async fn send(&self, email: &Email) -> anyhow::Result<()>;
+}
+
+/// 只把邮件内容输出到日志，未配置邮件服务时使用
+#[derive(Debug)]
+pub struct LogMailer;
+
+#[async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, email: &Email) -> anyhow::Result<()> {
+        tracing::info!("发送邮件至 {}: {}\n{}", email.to, email.subject, email.body);
+        Ok(())
+    }
+}
+
+pub fn from_config(config: &MailConfig) -> anyhow::Result<Arc<dyn Mailer>> {
+    Ok(match config.transport {
+        MailTransport::Smtp => Arc::new(SmtpMailer::new(config)?),
+        MailTransport::File => Arc::new(FileMailer::new(config)),
+        MailTransport::Log => Arc::new(LogMailer),
+    })
+}
@@ -1,30 +1,27 @@
+use std::net::SocketAddr;
+
 use anyhow::Result;
 use tower_http::{cors::CorsLayer, trace::TraceLayer};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-mod config;
-mod database;
-mod handlers;
-mod middleware;
-mod models;
-mod routes;
-mod services;
-mod utils;
-
-use config::Config;
+use server::{config::Config, database, routes, services::JwtKeyService, utils};
 
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::registry()
         .with(
             tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| {
-                "rikkahub_server=debug,tower_http=debug,axum::rejection=trace,sqlx=info".into()
+                "rikkahub_server=debug,server=debug,audit=info,tower_http=debug,axum::rejection=trace,sqlx=info".into()
             }),
         )
         .with(tracing_subscriber::fmt::layer())
         .init();
 
-    let config = Config::from_env()?;
+    let mut config = Config::from_env()?;
+    if let Some(password) = admin_password_arg() {
+        config.bootstrap.admin_password = Some(password);
+    }
+    utils::init_password_hashing(&config.password_hash)?;
     tracing::info!("配置加载完成");
 
     let pool = database::create_pool(&config.database).await?;
@@ -33,9 +30,17 @@ async fn main() -> Result<()> {
     database::run_migrations(&pool).await?;
     tracing::info!("数据库迁移完成");
 
-    database::seed(&pool).await?;
+    database::seed(&pool, &config.bootstrap).await?;
+
+    if std::env::args().nth(1).as_deref() == Some("rotate-jwt-key") {
+        let key = JwtKeyService::rotate(&pool, &config.jwt).await?;
+        println!("新的签名密钥: {}", key.kid);
+        println!("运行中的实例将在 5 分钟内自动加载新密钥");
+        return Ok(());
+    }
 
     let app = routes::create_router(pool, config.clone())
+        .await?
         .layer(CorsLayer::permissive())
         .layer(TraceLayer::new_for_http());
 
@@ -43,7 +48,25 @@ async fn main() -> Result<()> {
     tracing::info!("服务器启动，监听地址: {}", addr);
 
     let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
+
+/// 读取命令行参数 `--admin-password <密码>` 或 `--admin-password=<密码>`
+fn admin_password_arg() -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--admin-password" {
+            return args.next();
+        }
+        if let Some(password) = arg.strip_prefix("--admin-password=") {
+            return Some(password.to_string());
+        }
+    }
+    None
+}
@@ -1,30 +1,104 @@
+use std::sync::Arc;
+
 use axum::{
-    Json,
     extract::FromRequestParts,
-    http::{StatusCode, request::Parts},
+    http::request::Parts,
     response::{IntoResponse, Response},
 };
-use serde_json::json;
+use serde::Serialize;
 use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::{
     config::Config,
-    models::{User, UserStatus},
-    services::UserService,
-    utils::jwt::decode_token,
+    error::{AppError, ErrorCode},
+    i18n::Locale,
+    mail::Mailer,
+    middleware::{ClientIp, RequestMeta, locale},
+    models::{ApiKey, AuditEvent, User, UserStatus},
+    services::{
+        ApiKeyService, JwtKeyService, PermissionService, SettingService, UserService, setting::keys,
+    },
+    utils::{
+        api_key::{hash_api_key, is_api_key},
+        jwt::{JwtKeys, TokenPurpose},
+    },
 };
 
 #[derive(Debug, Clone)]
 pub struct AppState {
     pub pool: PgPool,
     pub config: Config,
+    pub http: reqwest::Client,
+    pub mailer: Arc<dyn Mailer>,
+    pub jwt_keys: JwtKeys,
+}
+
+/// 令牌可用于正常访问前，用户必须先完成的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequiredAction {
+    TwoFactorSetup,
+    PasswordChange,
 }
 
 #[derive(Debug)]
 pub struct AuthUser {
     pub user_id: Uuid,
     pub user: User,
+    /// 通过 API Key 认证时为对应的 key
+    pub api_key: Option<ApiKey>,
+    pub required_action: Option<RequiredAction>,
+    /// 管理员模拟登录时为实际操作的管理员
+    pub impersonator: Option<User>,
+}
+
+/// 允许受限令牌访问的认证用户，仅用于完成 [`RequiredAction`] 的接口
+#[derive(Debug)]
+pub struct RestrictedAuthUser(pub AuthUser);
+
+impl AuthUser {
+    /// 检查用户权限；使用 API Key 认证时还需落在 key 的 scopes 之内
+    pub async fn has_permission(
+        &self,
+        pool: &PgPool,
+        permission: &str,
+    ) -> Result<bool, sqlx::Error> {
+        if let Some(api_key) = &self.api_key
+            && !PermissionService::is_granted(&api_key.scopes, permission)
+        {
+            return Ok(false);
+        }
+
+        PermissionService::user_has_permission(pool, self.user_id, permission).await
+    }
+
+    /// 创建以当前用户为操作者的审计事件，模拟登录时同时记录管理员
+    pub fn audit_event(&self, meta: &RequestMeta, action: &str) -> AuditEvent {
+        meta.audit_event(action)
+            .actor(&self.user)
+            .impersonator(self.impersonator.as_ref())
+    }
+
+    /// 系统设置要求验证邮箱时，拒绝邮箱未验证的用户使用对话等功能
+    pub async fn require_verified_email(&self, pool: &PgPool) -> Result<(), AuthError> {
+        if self.user.email_verified_at.is_some() {
+            return Ok(());
+        }
+
+        let required = SettingService::get_or(pool, keys::AUTH_REQUIRE_VERIFIED_EMAIL, false)
+            .await
+            .map_err(|e| {
+                tracing::error!("数据库查询失败: {}", e);
+                AuthError::InternalError
+            })?;
+
+        if required {
+            return Err(AuthError::EmailNotVerified);
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
@@ -34,21 +108,46 @@ pub enum AuthError {
     ExpiredToken,
     UserNotFound,
     UserDisabled,
+    SessionRevoked,
+    EmailNotVerified,
+    IpNotAllowed,
+    ActionRequired(RequiredAction),
     InternalError,
 }
 
+impl From<AuthError> for AppError {
+    fn from(e: AuthError) -> Self {
+        match e {
+            AuthError::MissingToken => AppError::new(ErrorCode::MissingToken, "auth.missing_token"),
+            AuthError::InvalidToken => AppError::new(ErrorCode::InvalidToken, "auth.invalid_token"),
+            AuthError::ExpiredToken => AppError::new(ErrorCode::TokenExpired, "auth.token_expired"),
+            AuthError::UserNotFound => AppError::new(ErrorCode::InvalidToken, "user.not_found"),
+            AuthError::UserDisabled => AppError::new(ErrorCode::AccountDisabled, "user.disabled"),
+            AuthError::SessionRevoked => {
+                AppError::new(ErrorCode::SessionRevoked, "auth.session_revoked")
+            }
+            AuthError::EmailNotVerified => {
+                AppError::new(ErrorCode::EmailNotVerified, "auth.email_not_verified")
+            }
+            AuthError::IpNotAllowed => {
+                AppError::new(ErrorCode::IpNotAllowed, "auth.ip_not_allowed")
+            }
+            AuthError::ActionRequired(action) => {
+                let message = match action {
+                    RequiredAction::TwoFactorSetup => "auth.two_factor_setup_required",
+                    RequiredAction::PasswordChange => "auth.password_change_required",
+                };
+                AppError::new(ErrorCode::ActionRequired, message)
+                    .with_extra("required_action", action)
+            }
+            AuthError::InternalError => AppError::new(ErrorCode::Internal, "error.internal"),
+        }
+    }
+}
+
 impl IntoResponse for AuthError {
     fn into_response(self) -> Response {
-        let (status, message) = match self {
-            Self::MissingToken => (StatusCode::UNAUTHORIZED, "缺少认证令牌"),
-            Self::InvalidToken => (StatusCode::UNAUTHORIZED, "无效的认证令牌"),
-            Self::ExpiredToken => (StatusCode::UNAUTHORIZED, "认证令牌已过期"),
-            Self::UserNotFound => (StatusCode::UNAUTHORIZED, "用户不存在"),
-            Self::UserDisabled => (StatusCode::FORBIDDEN, "用户已被禁用"),
-            Self::InternalError => (StatusCode::INTERNAL_SERVER_ERROR, "内部服务器错误"),
-        };
-
-        (status, Json(json!({ "error": message }))).into_response()
+        AppError::from(self).into_response()
     }
 }
 
@@ -59,17 +158,45 @@ impl FromRequestParts<AppState> for AuthUser {
         parts: &mut Parts,
         state: &AppState,
     ) -> Result<Self, Self::Rejection> {
-        let auth_header = parts
-            .headers
-            .get("Authorization")
-            .and_then(|v| v.to_str().ok())
-            .ok_or(AuthError::MissingToken)?;
+        let auth = authenticate(parts, state).await?;
+
+        if let Some(action) = auth.required_action {
+            return Err(AuthError::ActionRequired(action));
+        }
+
+        Ok(auth)
+    }
+}
+
+impl FromRequestParts<AppState> for RestrictedAuthUser {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        authenticate(parts, state).await.map(RestrictedAuthUser)
+    }
+}
+
+async fn authenticate(parts: &Parts, state: &AppState) -> Result<AuthUser, AuthError> {
+    let auth_header = parts
+        .headers
+        .get("Authorization")
+        .and_then(|v| v.to_str().ok())
+        .ok_or(AuthError::MissingToken)?;
+
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or(AuthError::InvalidToken)?;
 
-        let token = auth_header
-            .strip_prefix("Bearer ")
-            .ok_or(AuthError::InvalidToken)?;
+    if is_api_key(token) {
+        return authenticate_api_key(parts, state, token).await;
+    }
 
-        let claims = decode_token(token, &state.config.jwt.secret)
+    let claims =
+        JwtKeyService::decode_token(&state.pool, &state.config.jwt, &state.jwt_keys, token)
+            .await
             .map_err(|e| {
                 tracing::debug!("JWT 解码失败: {}", e);
                 if e.to_string().contains("expired") {
@@ -79,21 +206,144 @@ impl FromRequestParts<AppState> for AuthUser {
                 }
             })?;
 
-        let user: User = UserService::find_by_id(&state.pool, claims.sub)
-            .await
-            .map_err(|e| {
-                tracing::error!("数据库查询失败: {}", e);
-                AuthError::InternalError
-            })?
-            .ok_or(AuthError::UserNotFound)?;
+    let required_action = match claims.purpose {
+        None => None,
+        Some(TokenPurpose::MfaEnrollment) => Some(RequiredAction::TwoFactorSetup),
+        Some(TokenPurpose::MfaChallenge) => return Err(AuthError::InvalidToken),
+    };
 
-        if user.status != UserStatus::Active as i16 {
-            return Err(AuthError::UserDisabled);
+    let user = load_active_user(&state.pool, claims.sub).await?;
+
+    if password_changed_since(&user, claims.iat) {
+        return Err(AuthError::SessionRevoked);
+    }
+
+    let impersonator = match claims.act {
+        Some(act) => Some(load_impersonator(&state.pool, act.sub, claims.iat).await?),
+        None => None,
+    };
+
+    let required_action = required_action.or(password_change_required(&user));
+    // 模拟登录时界面语言跟随实际操作的管理员
+    apply_locale_preference(impersonator.as_ref().unwrap_or(&user));
+
+    Ok(AuthUser {
+        user_id: claims.sub,
+        user,
+        api_key: None,
+        required_action,
+        impersonator,
+    })
+}
+
+async fn authenticate_api_key(
+    parts: &Parts,
+    state: &AppState,
+    token: &str,
+) -> Result<AuthUser, AuthError> {
+    let api_key = ApiKeyService::find_by_hash(&state.pool, &hash_api_key(token))
+        .await
+        .map_err(|e| {
+            tracing::error!("数据库查询失败: {}", e);
+            AuthError::InternalError
+        })?
+        .ok_or(AuthError::InvalidToken)?;
+
+    if api_key.is_expired() {
+        return Err(AuthError::ExpiredToken);
+    }
+
+    let ClientIp(ip) = ClientIp::from_parts(parts, &state.config.server);
+    if !api_key.allowed_ips.is_empty() {
+        let allowed = ip.is_some_and(|ip| {
+            api_key.allowed_ips.iter().any(|entry| {
+                entry
+                    .parse::<ipnet::IpNet>()
+                    .map(|net| net.contains(&ip))
+                    .or_else(|_| entry.parse::<std::net::IpAddr>().map(|a| a == ip))
+                    .unwrap_or(false)
+            })
+        });
+
+        if !allowed {
+            return Err(AuthError::IpNotAllowed);
         }
+    }
 
-        Ok(AuthUser {
-            user_id: claims.sub,
-            user,
-        })
+    let user = load_active_user(&state.pool, api_key.user_id).await?;
+    apply_locale_preference(&user);
+
+    if let Err(e) = ApiKeyService::touch(
+        &state.pool,
+        api_key.id,
+        ip.map(|ip| ip.to_string()).as_deref(),
+    )
+    .await
+    {
+        tracing::warn!("更新 API Key 使用记录失败: {}", e);
+    }
+
+    Ok(AuthUser {
+        user_id: user.id,
+        required_action: password_change_required(&user),
+        user,
+        api_key: Some(api_key),
+        impersonator: None,
+    })
+}
+
+/// 用户设置了语言偏好时覆盖按 `Accept-Language` 选择的语言
+fn apply_locale_preference(user: &User) {
+    if let Some(preferred) = user.locale.as_deref().and_then(Locale::parse) {
+        locale::prefer(preferred);
+    }
+}
+
+fn password_changed_since(user: &User, iat: usize) -> bool {
+    user.password_changed_at
+        .is_some_and(|changed| (iat as i64) < changed.timestamp())
+}
+
+/// 模拟登录令牌在管理员被禁用、修改密码或失去模拟权限后立即失效
+async fn load_impersonator(pool: &PgPool, actor_id: Uuid, iat: usize) -> Result<User, AuthError> {
+    let actor = load_active_user(pool, actor_id)
+        .await
+        .map_err(|_| AuthError::InvalidToken)?;
+
+    if password_changed_since(&actor, iat) {
+        return Err(AuthError::SessionRevoked);
     }
+
+    let allowed = PermissionService::user_has_permission(pool, actor.id, "admin.impersonate")
+        .await
+        .map_err(|e| {
+            tracing::error!("数据库查询失败: {}", e);
+            AuthError::InternalError
+        })?;
+    if !allowed {
+        return Err(AuthError::InvalidToken);
+    }
+
+    Ok(actor)
+}
+
+fn password_change_required(user: &User) -> Option<RequiredAction> {
+    user.must_change_password
+        .then_some(RequiredAction::PasswordChange)
+}
+
+async fn load_active_user(pool: &PgPool, user_id: Uuid) -> Result<User, AuthError> {
+    let user: User = UserService::find_by_id(pool, user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("数据库查询失败: {}", e);
+            AuthError::InternalError
+        })?
+        .ok_or(AuthError::UserNotFound)?;
+
+    if user.status != UserStatus::Active as i16 {
+        return Err(AuthError::UserDisabled);
+    }
+
+    Ok(user)
 }
@@ -0,0 +1,102 @@
+use std::{convert::Infallible, net::IpAddr, net::SocketAddr};
+
+use axum::{
+    extract::{ConnectInfo, FromRequestParts},
+    http::request::Parts,
+};
+
+use ipnet::IpNet;
+
+use crate::{config::ServerConfig, middleware::AppState};
+
+/// 客户端 IP，无法确定时为 `None`
+#[derive(Debug, Clone, Copy)]
+pub struct ClientIp(pub Option<IpAddr>);
+
+impl ClientIp {
+    pub fn from_parts(parts: &Parts, config: &ServerConfig) -> Self {
+        if config.trust_proxy {
+            let forwarded = match parts
+                .headers
+                .get("X-Forwarded-For")
+                .and_then(|v| v.to_str().ok())
+            {
+                Some(header) => forwarded_client(header, &config.trusted_proxies),
+                None => parts
+                    .headers
+                    .get("X-Real-IP")
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.trim().parse().ok()),
+            };
+
+            if forwarded.is_some() {
+                return Self(forwarded);
+            }
+        }
+
+        Self(
+            parts
+                .extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip()),
+        )
+    }
+}
+
+/// 代理把来源地址追加到 X-Forwarded-For 末尾，左侧的值由客户端控制，
+/// 因此从右向左跳过受信任的代理，取第一个其他地址
+fn forwarded_client(header: &str, trusted_proxies: &[IpNet]) -> Option<IpAddr> {
+    let mut last = None;
+    for entry in header.rsplit(',') {
+        let ip: IpAddr = entry.trim().parse().ok()?;
+        if !trusted_proxies.iter().any(|net| net.contains(&ip)) {
+            return Some(ip);
+        }
+        last = Some(ip);
+    }
+    last
+}
+
+impl FromRequestParts<AppState> for ClientIp {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        Ok(Self::from_parts(parts, &state.config.server))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> Option<IpAddr> {
+        Some(s.parse().unwrap())
+    }
+
+    #[test]
+    fn takes_rightmost_entry_appended_by_proxy() {
+        assert_eq!(forwarded_client("1.1.1.1, 2.2.2.2", &[]), ip("2.2.2.2"));
+        assert_eq!(forwarded_client("2.2.2.2", &[]), ip("2.2.2.2"));
+    }
+
+    #[test]
+    fn skips_trusted_proxies_from_the_right() {
+        let trusted: Vec<IpNet> = vec!["10.0.0.0/8".parse().unwrap()];
+        assert_eq!(
+            forwarded_client("1.1.1.1, 2.2.2.2, 10.0.0.5", &trusted),
+            ip("2.2.2.2")
+        );
+        assert_eq!(
+            forwarded_client("10.0.0.7, 10.0.0.5", &trusted),
+            ip("10.0.0.7")
+        );
+    }
+
+    #[test]
+    fn rejects_unparsable_hop() {
+        assert_eq!(forwarded_client("1.1.1.1, garbage", &[]), None);
+    }
+}
@@ -0,0 +1,100 @@
+use axum::{
+    extract::{
+        FromRequest, FromRequestParts,
+        rejection::{JsonRejection, PathRejection, QueryRejection},
+    },
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+
+use crate::{
+    error::{AppError, ErrorCode},
+    i18n::Message,
+};
+
+/// 替代 `axum::Json`，请求体解析失败时返回统一的错误格式
+#[derive(Debug, FromRequest)]
+#[from_request(via(axum::Json), rejection(AppError))]
+pub struct Json<T>(pub T);
+
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        axum::Json(self.0).into_response()
+    }
+}
+
+/// 替代 `axum::extract::Path`，路径参数无效时返回统一的错误格式
+#[derive(Debug, FromRequestParts)]
+#[from_request(via(axum::extract::Path), rejection(AppError))]
+pub struct Path<T>(pub T);
+
+/// 替代 `axum::extract::Query`，查询参数无效时返回统一的错误格式
+#[derive(Debug, FromRequestParts)]
+#[from_request(via(axum::extract::Query), rejection(AppError))]
+pub struct Query<T>(pub T);
+
+/// 取最内层的错误信息，去掉 axum 附加的英文前缀
+fn detail(e: &dyn std::error::Error) -> String {
+    let mut inner = e;
+    while let Some(source) = inner.source() {
+        inner = source;
+    }
+    inner.to_string()
+}
+
+/// 保留 axum 原有的状态码，只统一响应格式
+fn rejection(status: StatusCode, code: ErrorCode, message: Message) -> AppError {
+    AppError::new(code, message).with_status(status)
+}
+
+impl From<JsonRejection> for AppError {
+    fn from(e: JsonRejection) -> Self {
+        let status = e.status();
+        match &e {
+            JsonRejection::JsonDataError(inner) => rejection(
+                status,
+                ErrorCode::ValidationFailed,
+                Message::new("error.invalid_body").arg("detail", detail(inner)),
+            ),
+            JsonRejection::JsonSyntaxError(inner) => rejection(
+                status,
+                ErrorCode::BadRequest,
+                Message::new("error.invalid_json").arg("detail", detail(inner)),
+            ),
+            JsonRejection::MissingJsonContentType(_) => rejection(
+                status,
+                ErrorCode::BadRequest,
+                Message::new("error.json_content_type"),
+            ),
+            _ => rejection(
+                status,
+                ErrorCode::BadRequest,
+                Message::new("error.invalid_body").arg("detail", detail(&e)),
+            ),
+        }
+    }
+}
+
+impl From<PathRejection> for AppError {
+    fn from(e: PathRejection) -> Self {
+        match &e {
+            PathRejection::FailedToDeserializePathParams(inner) => rejection(
+                e.status(),
+                ErrorCode::ValidationFailed,
+                Message::new("error.invalid_path").arg("detail", inner.kind()),
+            ),
+            _ => AppError::internal(e.body_text()),
+        }
+    }
+}
+
+impl From<QueryRejection> for AppError {
+    fn from(e: QueryRejection) -> Self {
+        rejection(
+            e.status(),
+            ErrorCode::ValidationFailed,
+            Message::new("error.invalid_query").arg("detail", detail(&e)),
+        )
+    }
+}
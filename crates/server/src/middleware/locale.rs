@@ -0,0 +1,47 @@
+use std::cell::Cell;
+
+use axum::{
+    extract::Request,
+    http::{
+        HeaderValue,
+        header::{ACCEPT_LANGUAGE, CONTENT_LANGUAGE, VARY},
+    },
+    middleware::Next,
+    response::Response,
+};
+
+use crate::i18n::Locale;
+
+tokio::task_local! {
+    static LOCALE: Cell<Locale>;
+}
+
+/// 当前请求使用的语言，不在请求处理过程中调用时为默认语言
+pub fn current() -> Locale {
+    LOCALE.try_with(Cell::get).unwrap_or_default()
+}
+
+/// 认证后以用户的语言偏好覆盖 `Accept-Language`
+pub fn prefer(locale: Locale) {
+    let _ = LOCALE.try_with(|cell| cell.set(locale));
+}
+
+/// 按 `Accept-Language` 选择本次请求的语言，并在 `Content-Language` 中返回实际使用的语言
+pub async fn negotiate(request: Request, next: Next) -> Response {
+    let locale = request
+        .headers()
+        .get(ACCEPT_LANGUAGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(Locale::negotiate)
+        .unwrap_or_default();
+
+    LOCALE
+        .scope(Cell::new(locale), async move {
+            let mut response = next.run(request).await;
+            let headers = response.headers_mut();
+            headers.insert(CONTENT_LANGUAGE, HeaderValue::from_static(current().tag()));
+            headers.append(VARY, HeaderValue::from_static("accept-language"));
+            response
+        })
+        .await
+}
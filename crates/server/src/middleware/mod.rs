@@ -1,3 +1,11 @@
 pub mod auth;
+pub mod client_ip;
+pub mod extract;
+pub mod locale;
+pub mod request_id;
+pub mod request_meta;
 
-pub use auth::{AppState, AuthError, AuthUser};
+pub use auth::{AppState, AuthError, AuthUser, RequiredAction, RestrictedAuthUser};
+pub use client_ip::ClientIp;
+pub use extract::{Json, Path, Query};
+pub use request_meta::RequestMeta;
@@ -0,0 +1,45 @@
+use std::{convert::Infallible, net::IpAddr};
+
+use axum::{
+    extract::FromRequestParts,
+    http::{header::USER_AGENT, request::Parts},
+};
+
+use crate::{
+    middleware::{AppState, ClientIp},
+    models::AuditEvent,
+};
+
+/// 写入审计日志所需的请求信息
+#[derive(Debug, Clone)]
+pub struct RequestMeta {
+    pub ip: Option<IpAddr>,
+    pub user_agent: Option<String>,
+}
+
+impl RequestMeta {
+    /// 创建带有本次请求 IP 和 User-Agent 的审计事件
+    pub fn audit_event(&self, action: &str) -> AuditEvent {
+        AuditEvent::new(action).request(self.ip, self.user_agent.as_deref())
+    }
+}
+
+impl FromRequestParts<AppState> for RequestMeta {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let ClientIp(ip) = ClientIp::from_parts(parts, &state.config.server);
+
+        Ok(Self {
+            ip,
+            user_agent: parts
+                .headers
+                .get(USER_AGENT)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string),
+        })
+    }
+}
@@ -0,0 +1,79 @@
+use std::net::IpAddr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::models::User;
+
+/// User-Agent 的最大保存长度
+const MAX_USER_AGENT_LEN: usize = 512;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AuditEvent {
+    pub id: Uuid,
+    pub action: String,
+    pub actor_id: Option<Uuid>,
+    pub actor_name: Option<String>,
+    pub impersonator_id: Option<Uuid>,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub diff: Option<JsonValue>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AuditEvent {
+    pub fn new(action: &str) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            action: action.to_string(),
+            actor_id: None,
+            actor_name: None,
+            impersonator_id: None,
+            target_type: None,
+            target_id: None,
+            ip: None,
+            user_agent: None,
+            diff: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn actor(mut self, user: &User) -> Self {
+        self.actor_id = Some(user.id);
+        self.actor_name = Some(user.username.clone());
+        self
+    }
+
+    /// 未能确定用户时只记录名称，如登录失败时尝试的用户名
+    pub fn actor_name(mut self, name: &str) -> Self {
+        self.actor_name = Some(name.chars().take(255).collect());
+        self
+    }
+
+    pub fn impersonator(mut self, user: Option<&User>) -> Self {
+        self.impersonator_id = user.map(|u| u.id);
+        self
+    }
+
+    pub fn target(mut self, target_type: &str, target_id: impl ToString) -> Self {
+        self.target_type = Some(target_type.to_string());
+        self.target_id = Some(target_id.to_string());
+        self
+    }
+
+    pub fn request(mut self, ip: Option<IpAddr>, user_agent: Option<&str>) -> Self {
+        self.ip = ip.map(|ip| ip.to_string());
+        self.user_agent = user_agent.map(|ua| ua.chars().take(MAX_USER_AGENT_LEN).collect());
+        self
+    }
+
+    pub fn diff(mut self, diff: JsonValue) -> Self {
+        self.diff = Some(diff);
+        self
+    }
+}
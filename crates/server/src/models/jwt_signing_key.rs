@@ -0,0 +1,28 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct JwtSigningKey {
+    pub kid: String,
+    pub algorithm: String,
+    #[serde(skip_serializing)]
+    pub private_key: String,
+    pub public_jwk: JsonValue,
+    pub retired_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl JwtSigningKey {
+    pub fn new(kid: String, algorithm: String, private_key: String, public_jwk: JsonValue) -> Self {
+        Self {
+            kid,
+            algorithm,
+            private_key,
+            public_jwk,
+            retired_at: None,
+            created_at: Utc::now(),
+        }
+    }
+}
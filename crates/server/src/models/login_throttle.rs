@@ -0,0 +1,20 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct LoginThrottle {
+    pub scope: String,
+    pub key: String,
+    pub failures: i32,
+    pub last_failure_at: DateTime<Utc>,
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+impl LoginThrottle {
+    /// 距离允许再次尝试的剩余秒数，未被限制时返回 `None`
+    pub fn retry_after(&self) -> Option<i64> {
+        let remaining = (self.locked_until? - Utc::now()).num_seconds();
+        (remaining > 0).then_some(remaining)
+    }
+}
@@ -1,11 +1,35 @@
+mod api_key;
+mod audit_event;
+mod email_verification_token;
 mod group;
 mod group_permission;
+mod jwt_signing_key;
+mod login_throttle;
+mod oidc_state;
+mod passkey;
+mod password_reset_token;
+mod recovery_code;
 mod setting;
 mod user;
 mod user_group;
+mod user_identity;
+mod user_totp;
+mod webauthn_challenge;
 
+pub use api_key::ApiKey;
+pub use audit_event::AuditEvent;
+pub use email_verification_token::EmailVerificationToken;
 pub use group::Group;
 pub use group_permission::GroupPermission;
+pub use jwt_signing_key::JwtSigningKey;
+pub use login_throttle::LoginThrottle;
+pub use oidc_state::OidcState;
+pub use passkey::Passkey;
+pub use password_reset_token::PasswordResetToken;
+pub use recovery_code::RecoveryCode;
 pub use setting::{Setting, SettingType};
 pub use user::{User, UserStatus};
 pub use user_group::UserGroup;
+pub use user_identity::UserIdentity;
+pub use user_totp::UserTotp;
+pub use webauthn_challenge::WebauthnChallenge;
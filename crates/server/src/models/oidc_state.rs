@@ -0,0 +1,25 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// 尚未完成的 OIDC 授权请求
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct OidcState {
+    pub state: String,
+    pub provider: String,
+    pub code_verifier: String,
+    pub nonce: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl OidcState {
+    pub fn new(state: String, provider: String, code_verifier: String, nonce: String) -> Self {
+        Self {
+            state,
+            provider,
+            code_verifier,
+            nonce,
+            created_at: Utc::now(),
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Passkey {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    #[serde(skip_serializing)]
+    pub credential_id: Vec<u8>,
+    #[serde(skip_serializing)]
+    pub credential: JsonValue,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Passkey {
+    pub fn new(user_id: Uuid, name: String, credential_id: Vec<u8>, credential: JsonValue) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            user_id,
+            name,
+            credential_id,
+            credential,
+            last_used_at: None,
+            created_at: Utc::now(),
+        }
+    }
+}
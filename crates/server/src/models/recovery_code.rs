@@ -0,0 +1,26 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct RecoveryCode {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    #[serde(skip_serializing)]
+    pub code_hash: String,
+    pub used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl RecoveryCode {
+    pub fn new(user_id: Uuid, code_hash: String) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            user_id,
+            code_hash,
+            used_at: None,
+            created_at: Utc::now(),
+        }
+    }
+}
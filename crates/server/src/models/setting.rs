@@ -12,6 +12,27 @@ pub enum SettingType {
     Json,
 }
 
+impl SettingType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SettingType::Bool => "bool",
+            SettingType::String => "string",
+            SettingType::Int => "int",
+            SettingType::Json => "json",
+        }
+    }
+
+    /// 根据 JSON 值推断设置类型
+    pub fn of(value: &JsonValue) -> Self {
+        match value {
+            JsonValue::Bool(_) => SettingType::Bool,
+            JsonValue::String(_) => SettingType::String,
+            JsonValue::Number(n) if n.is_i64() => SettingType::Int,
+            _ => SettingType::Json,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Setting {
     pub key: String,
@@ -32,12 +53,7 @@ impl Setting {
         Self {
             key,
             value,
-            setting_type: match setting_type {
-                SettingType::Bool => "bool".to_string(),
-                SettingType::String => "string".to_string(),
-                SettingType::Int => "int".to_string(),
-                SettingType::Json => "json".to_string(),
-            },
+            setting_type: setting_type.as_str().to_string(),
             description,
             updated_at: Utc::now(),
         }
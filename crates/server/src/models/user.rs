@@ -16,11 +16,18 @@ pub struct User {
     pub id: Uuid,
     pub username: String,
     pub email: Option<String>,
+    pub email_verified_at: Option<DateTime<Utc>>,
     #[serde(skip_serializing)]
     pub password_hash: Option<String>,
     pub nickname: String,
     pub avatar: String,
     pub status: i16,
+    /// 为 true 时令牌只能用于修改密码
+    pub must_change_password: bool,
+    #[serde(skip_serializing)]
+    pub password_changed_at: Option<DateTime<Utc>>,
+    /// 语言偏好，为空时按请求的 Accept-Language 选择
+    pub locale: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub deleted_at: Option<DateTime<Utc>>,
@@ -33,10 +40,14 @@ impl User {
             id: Uuid::new_v4(),
             username: username.clone(),
             email: None,
+            email_verified_at: None,
             password_hash,
             nickname: username,
             avatar: String::new(),
             status: UserStatus::Active as i16,
+            must_change_password: false,
+            password_changed_at: None,
+            locale: None,
             created_at: now,
             updated_at: now,
             deleted_at: None,
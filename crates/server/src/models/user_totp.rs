@@ -0,0 +1,30 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct UserTotp {
+    pub user_id: Uuid,
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub last_used_step: i64,
+    pub enabled_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl UserTotp {
+    pub fn new(user_id: Uuid, secret: String) -> Self {
+        Self {
+            user_id,
+            secret,
+            last_used_step: 0,
+            enabled_at: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled_at.is_some()
+    }
+}
@@ -0,0 +1,28 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// 进行中的 WebAuthn 注册或认证流程
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct WebauthnChallenge {
+    pub id: Uuid,
+    /// 无用户名登录时为空
+    pub user_id: Option<Uuid>,
+    pub kind: String,
+    pub state: JsonValue,
+    pub created_at: DateTime<Utc>,
+}
+
+impl WebauthnChallenge {
+    pub fn new(user_id: Option<Uuid>, kind: String, state: JsonValue) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            user_id,
+            kind,
+            state,
+            created_at: Utc::now(),
+        }
+    }
+}
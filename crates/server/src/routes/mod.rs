@@ -1,10 +1,28 @@
-use axum::{Json, Router, routing::{get, post}};
+use std::time::Duration;
+
+use axum::{
+    Json, Router,
+    http::StatusCode,
+    routing::{delete, get, post, put},
+};
 use serde_json::{Value, json};
 use sqlx::PgPool;
 
-use crate::config::Config;
-use crate::handlers::{login, me};
-use crate::middleware::AppState;
+use crate::config::{Config, JwtAlgorithm};
+use crate::error::{AppError, ErrorCode};
+use crate::handlers::{
+    change_email, change_password, confirm_email, create_api_key, delete_api_key, delete_passkey,
+    delete_setting, disable_totp, enable_totp, export_audit_events, finish_passkey_login,
+    finish_passkey_registration, forgot_password, impersonate_user, jwks, list_api_keys,
+    list_audit_events, list_jwt_keys, list_oidc_providers, list_passkeys, list_settings, login,
+    login_2fa, me, oidc_authorize, oidc_callback, regenerate_recovery_codes, resend_verification,
+    reset_password, rotate_jwt_key, setup_totp, start_passkey_login, start_passkey_registration,
+    two_factor_status, unlock_ip, unlock_user, update_locale, update_setting,
+};
+use crate::mail;
+use crate::middleware::{AppState, locale, request_id};
+use crate::services::JwtKeyService;
+use crate::utils::jwt::JwtKeys;
 
 async fn health_check() -> Json<Value> {
     Json(json!({
@@ -13,12 +31,89 @@ async fn health_check() -> Json<Value> {
     }))
 }
 
-pub fn create_router(pool: PgPool, config: Config) -> Router {
-    let state = AppState { pool, config };
+/// 定期重新加载 JWT 密钥的间隔，使其他实例轮换的密钥及时生效
+const JWT_KEY_REFRESH_INTERVAL: Duration = Duration::from_secs(300);
+
+pub async fn create_router(pool: PgPool, config: Config) -> anyhow::Result<Router> {
+    let jwt_keys = JwtKeys::new(JwtKeyService::load(&pool, &config.jwt).await?);
+
+    if config.jwt.algorithm != JwtAlgorithm::Hs256 {
+        let (pool, config, jwt_keys) = (pool.clone(), config.jwt.clone(), jwt_keys.clone());
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(JWT_KEY_REFRESH_INTERVAL);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if let Err(e) = JwtKeyService::reload(&pool, &config, &jwt_keys).await {
+                    tracing::error!("重新加载 JWT 密钥失败: {}", e);
+                }
+            }
+        });
+    }
 
-    Router::new()
+    let state = AppState {
+        mailer: mail::from_config(&config.mail)?,
+        jwt_keys,
+        pool,
+        config,
+        http: reqwest::Client::new(),
+    };
+
+    let router = Router::new()
         .route("/health", get(health_check))
+        .route("/.well-known/jwks.json", get(jwks))
         .route("/auth/login", post(login))
+        .route("/auth/login/2fa", post(login_2fa))
         .route("/auth/me", get(me))
-        .with_state(state)
+        .route("/auth/email", put(change_email))
+        .route("/auth/locale", put(update_locale))
+        .route("/auth/email/verification", post(resend_verification))
+        .route("/auth/email/confirm", post(confirm_email))
+        .route("/auth/password", put(change_password))
+        .route("/auth/password/forgot", post(forgot_password))
+        .route("/auth/password/reset", post(reset_password))
+        .route("/auth/2fa", get(two_factor_status))
+        .route("/auth/2fa/totp/setup", post(setup_totp))
+        .route("/auth/2fa/totp/enable", post(enable_totp))
+        .route("/auth/2fa/totp/disable", post(disable_totp))
+        .route("/auth/2fa/recovery-codes", post(regenerate_recovery_codes))
+        .route("/auth/passkeys", get(list_passkeys))
+        .route("/auth/passkeys/{id}", delete(delete_passkey))
+        .route(
+            "/auth/passkeys/register/start",
+            post(start_passkey_registration),
+        )
+        .route(
+            "/auth/passkeys/register/finish",
+            post(finish_passkey_registration),
+        )
+        .route("/auth/passkeys/login/start", post(start_passkey_login))
+        .route("/auth/passkeys/login/finish", post(finish_passkey_login))
+        .route("/auth/oidc/providers", get(list_oidc_providers))
+        .route("/auth/oidc/{provider}/authorize", get(oidc_authorize))
+        .route("/auth/oidc/{provider}/callback", get(oidc_callback))
+        .route("/api/keys", get(list_api_keys).post(create_api_key))
+        .route("/api/keys/{id}", delete(delete_api_key))
+        .route("/api/admin/users/{id}/lockout", delete(unlock_user))
+        .route("/api/admin/users/{id}/impersonate", post(impersonate_user))
+        .route("/api/admin/lockouts/ip/{ip}", delete(unlock_ip))
+        .route("/api/admin/audit-events", get(list_audit_events))
+        .route("/api/admin/audit-events/export", get(export_audit_events))
+        .route("/api/admin/jwt-keys", get(list_jwt_keys))
+        .route("/api/admin/jwt-keys/rotate", post(rotate_jwt_key))
+        .route("/api/admin/settings", get(list_settings))
+        .route(
+            "/api/admin/settings/{key}",
+            put(update_setting).delete(delete_setting),
+        )
+        .fallback(|| async { AppError::not_found("error.route_not_found") })
+        .method_not_allowed_fallback(|| async {
+            AppError::new(ErrorCode::BadRequest, "error.method_not_allowed")
+                .with_status(StatusCode::METHOD_NOT_ALLOWED)
+        })
+        .layer(axum::middleware::from_fn(locale::negotiate))
+        .layer(axum::middleware::from_fn(request_id::propagate))
+        .with_state(state);
+
+    Ok(router)
 }
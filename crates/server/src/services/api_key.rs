@@ -0,0 +1,77 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::ApiKey;
+
+pub struct ApiKeyService;
+
+impl ApiKeyService {
+    pub async fn find_by_hash(
+        pool: &PgPool,
+        key_hash: &str,
+    ) -> Result<Option<ApiKey>, sqlx::Error> {
+        sqlx::query_as("SELECT * FROM api_keys WHERE key_hash = $1 AND deleted_at IS NULL")
+            .bind(key_hash)
+            .fetch_optional(pool)
+            .await
+    }
+
+    pub async fn list_by_user(pool: &PgPool, user_id: Uuid) -> Result<Vec<ApiKey>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT * FROM api_keys WHERE user_id = $1 AND deleted_at IS NULL ORDER BY created_at DESC",
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn create(pool: &PgPool, api_key: &ApiKey) -> Result<ApiKey, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            INSERT INTO api_keys (id, user_id, name, prefix, key_hash, scopes, allowed_ips, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING *
+            "#,
+        )
+        .bind(api_key.id)
+        .bind(api_key.user_id)
+        .bind(&api_key.name)
+        .bind(&api_key.prefix)
+        .bind(&api_key.key_hash)
+        .bind(&api_key.scopes)
+        .bind(&api_key.allowed_ips)
+        .bind(api_key.expires_at)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// 软删除，返回是否删除成功
+    pub async fn revoke(pool: &PgPool, user_id: Uuid, id: Uuid) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE api_keys SET deleted_at = NOW() WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 记录最近使用情况，一分钟内重复请求不再写库
+    pub async fn touch(pool: &PgPool, id: Uuid, ip: Option<&str>) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE api_keys SET last_used_at = NOW(), last_used_ip = $2
+            WHERE id = $1
+              AND (last_used_at IS NULL OR last_used_at < NOW() - INTERVAL '1 minute')
+            "#,
+        )
+        .bind(id)
+        .bind(ip)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,154 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::models::AuditEvent;
+
+/// 审计事件类型
+pub mod action {
+    pub const LOGIN: &str = "auth.login";
+    pub const LOGIN_FAILED: &str = "auth.login_failed";
+    pub const IMPERSONATE: &str = "auth.impersonate";
+    pub const PASSWORD_CHANGE: &str = "user.password_change";
+    pub const PASSWORD_RESET: &str = "user.password_reset";
+    pub const EMAIL_CHANGE: &str = "user.email_change";
+    pub const TOTP_ENABLE: &str = "user.totp_enable";
+    pub const TOTP_DISABLE: &str = "user.totp_disable";
+    pub const RECOVERY_CODES_REGENERATE: &str = "user.recovery_codes_regenerate";
+    pub const PASSKEY_CREATE: &str = "user.passkey_create";
+    pub const PASSKEY_DELETE: &str = "user.passkey_delete";
+    pub const GROUPS_CHANGE: &str = "user.groups_change";
+    pub const UNLOCK: &str = "user.unlock";
+    pub const IP_UNLOCK: &str = "ip.unlock";
+    pub const API_KEY_CREATE: &str = "api_key.create";
+    pub const API_KEY_DELETE: &str = "api_key.delete";
+    pub const SETTING_UPDATE: &str = "setting.update";
+    pub const SETTING_DELETE: &str = "setting.delete";
+    pub const JWT_KEY_ROTATE: &str = "jwt_key.rotate";
+}
+
+/// 查询条件，`action` 支持 `auth.*` 形式的前缀匹配
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditQuery {
+    pub action: Option<String>,
+    pub actor_id: Option<Uuid>,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub ip: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+pub struct AuditService;
+
+impl AuditService {
+    /// 写入审计事件；写入失败只记录日志，不影响已完成的操作
+    pub async fn record(pool: &PgPool, event: AuditEvent) {
+        tracing::info!(
+            target: "audit",
+            action = %event.action,
+            actor = event.actor_name.as_deref().unwrap_or("-"),
+            target_type = event.target_type.as_deref().unwrap_or("-"),
+            target_id = event.target_id.as_deref().unwrap_or("-"),
+            ip = event.ip.as_deref().unwrap_or("-"),
+            "审计事件"
+        );
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO audit_events (
+                id, action, actor_id, actor_name, impersonator_id, target_type, target_id,
+                ip, user_agent, diff, created_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            "#,
+        )
+        .bind(event.id)
+        .bind(&event.action)
+        .bind(event.actor_id)
+        .bind(&event.actor_name)
+        .bind(event.impersonator_id)
+        .bind(&event.target_type)
+        .bind(&event.target_id)
+        .bind(&event.ip)
+        .bind(&event.user_agent)
+        .bind(&event.diff)
+        .bind(event.created_at)
+        .execute(pool)
+        .await;
+
+        if let Err(e) = result {
+            tracing::error!("写入审计日志失败: {} ({})", e, event.action);
+        }
+    }
+
+    /// 按时间倒序查询，`limit` 会被限制在 `max_limit` 以内
+    pub async fn list(
+        pool: &PgPool,
+        query: &AuditQuery,
+        max_limit: i64,
+    ) -> Result<Vec<AuditEvent>, sqlx::Error> {
+        let mut builder = QueryBuilder::new("SELECT * FROM audit_events");
+        Self::push_filters(&mut builder, query);
+        builder
+            .push(" ORDER BY created_at DESC, id LIMIT ")
+            .push_bind(query.limit.unwrap_or(max_limit).clamp(1, max_limit))
+            .push(" OFFSET ")
+            .push_bind(query.offset.unwrap_or(0).max(0));
+
+        builder.build_query_as().fetch_all(pool).await
+    }
+
+    pub async fn count(pool: &PgPool, query: &AuditQuery) -> Result<i64, sqlx::Error> {
+        let mut builder = QueryBuilder::new("SELECT COUNT(*) FROM audit_events");
+        Self::push_filters(&mut builder, query);
+
+        builder.build_query_scalar().fetch_one(pool).await
+    }
+
+    fn push_filters(builder: &mut QueryBuilder<'_, Postgres>, query: &AuditQuery) {
+        builder.push(" WHERE TRUE");
+
+        if let Some(action) = &query.action {
+            match action.strip_suffix('*') {
+                Some(prefix) => {
+                    let escaped = prefix
+                        .replace('\\', "\\\\")
+                        .replace('%', "\\%")
+                        .replace('_', "\\_");
+                    builder
+                        .push(" AND action LIKE ")
+                        .push_bind(format!("{}%", escaped));
+                }
+                None => {
+                    builder.push(" AND action = ").push_bind(action.clone());
+                }
+            }
+        }
+        if let Some(actor_id) = query.actor_id {
+            builder.push(" AND actor_id = ").push_bind(actor_id);
+        }
+        if let Some(target_type) = &query.target_type {
+            builder
+                .push(" AND target_type = ")
+                .push_bind(target_type.clone());
+        }
+        if let Some(target_id) = &query.target_id {
+            builder
+                .push(" AND target_id = ")
+                .push_bind(target_id.clone());
+        }
+        if let Some(ip) = &query.ip {
+            builder.push(" AND ip = ").push_bind(ip.clone());
+        }
+        if let Some(since) = query.since {
+            builder.push(" AND created_at >= ").push_bind(since);
+        }
+        if let Some(until) = query.until {
+            builder.push(" AND created_at < ").push_bind(until);
+        }
+    }
+}
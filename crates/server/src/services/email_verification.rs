@@ -0,0 +1,89 @@
+use chrono::{Duration, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{models::EmailVerificationToken, utils::random_hex};
+
+/// 验证令牌有效期（秒）
+pub const TOKEN_EXPIRES_IN: i64 = 24 * 3600;
+/// 同一用户两次发送验证邮件的最短间隔（秒）
+const RESEND_INTERVAL: i64 = 60;
+
+pub struct EmailVerificationService;
+
+impl EmailVerificationService {
+    fn hash_token(token: &str) -> String {
+        hex::encode(Sha256::digest(token.as_bytes()))
+    }
+
+    /// 为待验证的邮箱生成令牌，距上次生成不足间隔时返回 `None`。
+    /// 新令牌生成后该用户之前的令牌全部作废
+    pub async fn create(
+        pool: &PgPool,
+        user_id: Uuid,
+        email: &str,
+    ) -> Result<Option<String>, sqlx::Error> {
+        let (recent,): (bool,) = sqlx::query_as(
+            "SELECT EXISTS(SELECT 1 FROM email_verification_tokens WHERE user_id = $1 AND created_at > $2)",
+        )
+        .bind(user_id)
+        .bind(Utc::now() - Duration::seconds(RESEND_INTERVAL))
+        .fetch_one(pool)
+        .await?;
+
+        if recent {
+            return Ok(None);
+        }
+
+        let token = random_hex(32);
+        let record = EmailVerificationToken::new(
+            user_id,
+            email.to_string(),
+            Self::hash_token(&token),
+            Utc::now() + Duration::seconds(TOKEN_EXPIRES_IN),
+        );
+
+        let mut tx = pool.begin().await?;
+
+        sqlx::query("DELETE FROM email_verification_tokens WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO email_verification_tokens (id, user_id, email, token_hash, expires_at, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(record.id)
+        .bind(record.user_id)
+        .bind(&record.email)
+        .bind(&record.token_hash)
+        .bind(record.expires_at)
+        .bind(record.created_at)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(Some(token))
+    }
+
+    /// 取出并删除未过期的令牌，令牌只能使用一次
+    pub async fn consume(
+        pool: &PgPool,
+        token: &str,
+    ) -> Result<Option<EmailVerificationToken>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            DELETE FROM email_verification_tokens
+            WHERE token_hash = $1 AND expires_at > NOW()
+            RETURNING *
+            "#,
+        )
+        .bind(Self::hash_token(token))
+        .fetch_optional(pool)
+        .await
+    }
+}
@@ -0,0 +1,74 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::Group;
+
+pub struct GroupService;
+
+impl GroupService {
+    pub async fn find_by_name(pool: &PgPool, name: &str) -> Result<Option<Group>, sqlx::Error> {
+        sqlx::query_as("SELECT * FROM groups WHERE name = $1 AND deleted_at IS NULL")
+            .bind(name)
+            .fetch_optional(pool)
+            .await
+    }
+
+    pub async fn list_by_user(pool: &PgPool, user_id: Uuid) -> Result<Vec<Group>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT g.* FROM groups g
+            JOIN user_groups ug ON ug.group_id = g.id
+            WHERE ug.user_id = $1 AND g.deleted_at IS NULL
+            ORDER BY g.name
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// 将用户加入所有默认用户组
+    pub async fn add_user_to_default_groups(
+        pool: &PgPool,
+        user_id: Uuid,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO user_groups (user_id, group_id)
+            SELECT $1, id FROM groups WHERE is_default = true AND deleted_at IS NULL
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn add_user(pool: &PgPool, user_id: Uuid, group_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO user_groups (user_id, group_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+        )
+        .bind(user_id)
+        .bind(group_id)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove_user(
+        pool: &PgPool,
+        user_id: Uuid,
+        group_id: Uuid,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM user_groups WHERE user_id = $1 AND group_id = $2")
+            .bind(user_id)
+            .bind(group_id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+}
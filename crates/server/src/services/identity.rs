@@ -0,0 +1,205 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{Result, anyhow};
+use chrono::Utc;
+use serde_json::json;
+use sqlx::PgPool;
+
+use crate::{
+    models::{AuditEvent, User, UserIdentity},
+    services::{AuditService, GroupService, UserService, audit},
+    utils::random_hex,
+};
+
+/// 从 IdP 或目录服务获取到的外部身份
+#[derive(Debug, Clone)]
+pub struct ExternalIdentity {
+    pub subject: String,
+    pub email: Option<String>,
+    pub email_verified: bool,
+    pub preferred_username: Option<String>,
+    pub name: Option<String>,
+    pub groups: Vec<String>,
+}
+
+/// OIDC、LDAP 等外部身份源共用的本地账户关联逻辑
+pub struct IdentityService;
+
+impl IdentityService {
+    /// 查找或创建对应的本地用户，并同步外部用户组映射
+    ///
+    /// `provider` 为记录在 `user_identities` 中的身份源名称，
+    /// `group_mapping` 为外部用户组 -> RikkaHub 用户组
+    pub async fn resolve_user(
+        pool: &PgPool,
+        provider: &str,
+        group_mapping: &HashMap<String, String>,
+        identity: &ExternalIdentity,
+    ) -> Result<User> {
+        let verified_email = identity
+            .email
+            .as_deref()
+            .filter(|_| identity.email_verified);
+
+        let linked: Option<UserIdentity> =
+            sqlx::query_as("SELECT * FROM user_identities WHERE provider = $1 AND subject = $2")
+                .bind(provider)
+                .bind(&identity.subject)
+                .fetch_optional(pool)
+                .await?;
+
+        let user = match linked {
+            Some(linked) => {
+                sqlx::query(
+                    "UPDATE user_identities SET email = $2, updated_at = NOW() WHERE id = $1",
+                )
+                .bind(linked.id)
+                .bind(&identity.email)
+                .execute(pool)
+                .await?;
+
+                UserService::find_by_id(pool, linked.user_id)
+                    .await?
+                    .ok_or_else(|| anyhow!("关联的用户不存在"))?
+            }
+            None => {
+                let existing = match verified_email {
+                    Some(email) => UserService::find_by_email(pool, email).await?,
+                    None => None,
+                };
+
+                // 只关联本地同样已验证的邮箱，避免他人预先填写邮箱来接管账户；
+                // 邮箱被未验证的账户占用时新建的用户不带邮箱
+                let user = match existing {
+                    Some(user) if user.email_verified_at.is_some() => user,
+                    Some(_) => Self::provision_user(pool, provider, identity, None).await?,
+                    None => Self::provision_user(pool, provider, identity, verified_email).await?,
+                };
+
+                let link = UserIdentity::new(
+                    user.id,
+                    provider.to_string(),
+                    identity.subject.clone(),
+                    identity.email.clone(),
+                );
+                sqlx::query(
+                    "INSERT INTO user_identities (id, user_id, provider, subject, email) VALUES ($1, $2, $3, $4, $5)",
+                )
+                .bind(link.id)
+                .bind(link.user_id)
+                .bind(&link.provider)
+                .bind(&link.subject)
+                .bind(&link.email)
+                .execute(pool)
+                .await?;
+
+                user
+            }
+        };
+
+        Self::sync_groups(pool, provider, group_mapping, &user, &identity.groups).await?;
+
+        Ok(user)
+    }
+
+    /// 新建的用户不设置密码，只能通过外部身份源登录
+    async fn provision_user(
+        pool: &PgPool,
+        provider: &str,
+        identity: &ExternalIdentity,
+        verified_email: Option<&str>,
+    ) -> Result<User> {
+        let base: String = identity
+            .preferred_username
+            .as_deref()
+            .or_else(|| verified_email.and_then(|e| e.split('@').next()))
+            .unwrap_or(&identity.subject)
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+            .take(40)
+            .collect();
+        let base = if base.is_empty() {
+            provider
+                .chars()
+                .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+                .collect()
+        } else {
+            base
+        };
+
+        let mut username = base.clone();
+        while UserService::username_exists(pool, &username).await? {
+            username = format!("{}_{}", base, random_hex(3));
+        }
+
+        let mut user = User::new(username, None);
+        if let Some(name) = &identity.name {
+            user.nickname = name.chars().take(100).collect();
+        }
+        user.email = verified_email.map(str::to_string);
+        user.email_verified_at = verified_email.map(|_| Utc::now());
+
+        let user = UserService::create(pool, &user).await?;
+        GroupService::add_user_to_default_groups(pool, user.id).await?;
+
+        tracing::info!("通过 {} 自动创建用户: {}", provider, user.username);
+
+        Ok(user)
+    }
+
+    /// 只管理映射表中出现的用户组，其余成员关系保持不变
+    async fn sync_groups(
+        pool: &PgPool,
+        provider: &str,
+        group_mapping: &HashMap<String, String>,
+        user: &User,
+        external_groups: &[String],
+    ) -> Result<()> {
+        if group_mapping.is_empty() {
+            return Ok(());
+        }
+
+        let desired: HashSet<&str> = external_groups
+            .iter()
+            .filter_map(|g| group_mapping.get(g))
+            .map(String::as_str)
+            .collect();
+        let managed: HashSet<&str> = group_mapping.values().map(String::as_str).collect();
+        let current: HashSet<String> = GroupService::list_by_user(pool, user.id)
+            .await?
+            .into_iter()
+            .map(|g| g.name)
+            .collect();
+        let mut added = Vec::new();
+        let mut removed = Vec::new();
+
+        for name in managed {
+            let Some(group) = GroupService::find_by_name(pool, name).await? else {
+                tracing::warn!("用户组映射的目标用户组不存在: {}", name);
+                continue;
+            };
+
+            if desired.contains(name) {
+                if !current.contains(name) {
+                    GroupService::add_user(pool, user.id, group.id).await?;
+                    added.push(name);
+                }
+            } else if current.contains(name) {
+                GroupService::remove_user(pool, user.id, group.id).await?;
+                removed.push(name);
+            }
+        }
+
+        if !added.is_empty() || !removed.is_empty() {
+            added.sort_unstable();
+            removed.sort_unstable();
+            let event = AuditEvent::new(audit::action::GROUPS_CHANGE)
+                .actor_name(provider)
+                .target("user", user.id)
+                .diff(json!({ "source": provider, "added": added, "removed": removed }));
+            AuditService::record(pool, event).await;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use ldap3::{LdapConnAsync, LdapConnSettings, Scope, SearchEntry, dn_escape, ldap_escape};
+
+use crate::{config::LdapDirectoryConfig, services::identity::ExternalIdentity};
+
+/// 连接和单次操作的超时时间
+const LDAP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// LDAP resultCode: invalidCredentials
+const RC_INVALID_CREDENTIALS: u32 = 49;
+
+pub struct LdapService;
+
+impl LdapService {
+    /// 以用户身份绑定目录并读取用户条目，凭据错误时返回 `None`
+    pub async fn authenticate(
+        directory: &LdapDirectoryConfig,
+        username: &str,
+        password: &str,
+    ) -> Result<Option<ExternalIdentity>> {
+        // 空密码会被服务器当作匿名绑定而直接成功
+        if username.is_empty() || password.is_empty() {
+            return Ok(None);
+        }
+
+        let settings = LdapConnSettings::new()
+            .set_conn_timeout(LDAP_TIMEOUT)
+            .set_starttls(directory.starttls);
+        let (conn, mut ldap) = LdapConnAsync::with_settings(settings, &directory.url).await?;
+        ldap3::drive!(conn);
+
+        let bind_dn = directory
+            .bind_dn
+            .replace("{username}", &dn_escape(username));
+        let bind = ldap
+            .with_timeout(LDAP_TIMEOUT)
+            .simple_bind(&bind_dn, password)
+            .await?;
+        if bind.rc == RC_INVALID_CREDENTIALS {
+            return Ok(None);
+        }
+        bind.success()?;
+
+        let filter = directory
+            .user_filter
+            .replace("{username}", &ldap_escape(username));
+        let attributes = [
+            directory.username_attribute.as_str(),
+            directory.email_attribute.as_str(),
+            directory.name_attribute.as_str(),
+            directory.group_attribute.as_str(),
+        ];
+        let (entries, _) = ldap
+            .with_timeout(LDAP_TIMEOUT)
+            .search(&directory.base_dn, Scope::Subtree, &filter, attributes)
+            .await?
+            .success()?;
+        let _ = ldap.unbind().await;
+
+        let mut entries = entries.into_iter();
+        let entry = match (entries.next(), entries.next()) {
+            (Some(entry), None) => SearchEntry::construct(entry),
+            (None, _) => return Err(anyhow!("绑定成功但找不到用户条目: {}", filter)),
+            (Some(_), Some(_)) => return Err(anyhow!("过滤器匹配到多个用户条目: {}", filter)),
+        };
+
+        let values = |name: &str| -> Vec<String> {
+            entry
+                .attrs
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, values)| values.clone())
+                .unwrap_or_default()
+        };
+        let first = |name: &str| values(name).into_iter().find(|v| !v.is_empty());
+
+        Ok(Some(ExternalIdentity {
+            subject: entry.dn.to_lowercase(),
+            email: first(&directory.email_attribute),
+            email_verified: directory.trust_email,
+            preferred_username: first(&directory.username_attribute)
+                .or_else(|| Some(username.to_string())),
+            name: first(&directory.name_attribute),
+            groups: values(&directory.group_attribute)
+                .iter()
+                .map(|dn| dn.to_lowercase())
+                .collect(),
+        }))
+    }
+}
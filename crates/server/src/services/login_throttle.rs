@@ -0,0 +1,273 @@
+use std::net::IpAddr;
+
+use chrono::{DateTime, Duration, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+
+use crate::{
+    models::LoginThrottle,
+    services::{SettingService, setting::keys},
+};
+
+/// 登录限制的维度
+pub mod scope {
+    pub const USERNAME: &str = "username";
+    pub const IP: &str = "ip";
+}
+
+/// 超过该时长没有新的失败记录时计数归零（秒）
+const FAILURE_WINDOW: i64 = 24 * 3600;
+
+/// 登录失败锁定策略，取值来自系统设置
+#[derive(Debug, Clone, Copy)]
+pub struct LockoutPolicy {
+    pub threshold: i32,
+    pub ip_threshold: i32,
+    pub duration: i64,
+}
+
+impl LockoutPolicy {
+    pub async fn load(pool: &PgPool) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            threshold: SettingService::get_or(pool, keys::AUTH_LOCKOUT_THRESHOLD, 5).await?,
+            ip_threshold: SettingService::get_or(pool, keys::AUTH_LOCKOUT_IP_THRESHOLD, 20).await?,
+            duration: SettingService::get_or(pool, keys::AUTH_LOCKOUT_DURATION, 900).await?,
+        })
+    }
+
+    /// 第 `failures` 次失败后需要等待的秒数：达到阈值时锁定，
+    /// 之前按 2 的幂次递增，且不超过锁定时长
+    fn delay(&self, failures: i32, threshold: i32) -> i64 {
+        if threshold > 0 && failures >= threshold {
+            self.duration
+        } else if failures >= 2 {
+            2i64.saturating_pow((failures - 1) as u32)
+                .min(self.duration)
+        } else {
+            0
+        }
+    }
+}
+
+/// 预占的一次登录尝试，见 [`LoginThrottleService::reserve`]
+#[derive(Debug)]
+pub struct LoginAttempt {
+    reserved: Vec<Reserved>,
+}
+
+#[derive(Debug)]
+struct Reserved {
+    scope: &'static str,
+    key: String,
+    /// 本次失败是否触发锁定
+    locks: bool,
+    previous_locked_until: Option<DateTime<Utc>>,
+    locked_until: Option<DateTime<Utc>>,
+}
+
+impl LoginAttempt {
+    /// 凭据验证失败，失败次数已在预占时记录，这里只记录触发的锁定
+    pub fn failed(self) {
+        for reserved in self.reserved.iter().filter(|r| r.locks) {
+            tracing::warn!(
+                "登录失败次数过多，临时锁定 {} {}",
+                reserved.scope,
+                reserved.key
+            );
+        }
+    }
+}
+
+pub enum Reservation {
+    /// 已预先记为一次失败，验证通过后需调用 [`LoginThrottleService::release`]
+    Granted(LoginAttempt),
+    /// 仍需等待的秒数
+    Locked(i64),
+}
+
+pub struct LoginThrottleService;
+
+impl LoginThrottleService {
+    /// 用户名不区分大小写，避免通过变换大小写绕过限制；
+    /// 登录名未经校验，取哈希使任意长度的输入都能存入
+    pub fn username_key(username: &str) -> String {
+        hex::encode(Sha256::digest(username.trim().to_lowercase().as_bytes()))
+    }
+
+    pub async fn find(
+        pool: &PgPool,
+        scope: &str,
+        key: &str,
+    ) -> Result<Option<LoginThrottle>, sqlx::Error> {
+        sqlx::query_as("SELECT * FROM login_throttles WHERE scope = $1 AND key = $2")
+            .bind(scope)
+            .bind(key)
+            .fetch_optional(pool)
+            .await
+    }
+
+    /// 验证凭据前先为用户名和 IP 各记一次失败，使并发请求无法在锁定生效前绕过限制；
+    /// 任一维度仍被锁定时不计数，返回需等待的秒数
+    pub async fn reserve(
+        pool: &PgPool,
+        policy: &LockoutPolicy,
+        username: &str,
+        ip: Option<IpAddr>,
+    ) -> Result<Reservation, sqlx::Error> {
+        sqlx::query(
+            "DELETE FROM login_throttles WHERE last_failure_at < $1 AND (locked_until IS NULL OR locked_until < NOW())",
+        )
+        .bind(Utc::now() - Duration::seconds(FAILURE_WINDOW))
+        .execute(pool)
+        .await?;
+
+        let mut targets = vec![(
+            scope::USERNAME,
+            Self::username_key(username),
+            policy.threshold,
+        )];
+        if let Some(ip) = ip {
+            targets.push((scope::IP, ip.to_string(), policy.ip_threshold));
+        }
+
+        // 按固定顺序加行锁，同一用户名或 IP 的请求依次预占
+        let mut tx = pool.begin().await?;
+        let mut reserved = Vec::with_capacity(targets.len());
+        for (scope, key, threshold) in targets {
+            sqlx::query(
+                "INSERT INTO login_throttles (scope, key) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+            )
+            .bind(scope)
+            .bind(&key)
+            .execute(&mut *tx)
+            .await?;
+
+            let throttle: LoginThrottle = sqlx::query_as(
+                "SELECT * FROM login_throttles WHERE scope = $1 AND key = $2 FOR UPDATE",
+            )
+            .bind(scope)
+            .bind(&key)
+            .fetch_one(&mut *tx)
+            .await?;
+
+            if let Some(retry_after) = throttle.retry_after() {
+                tx.rollback().await?;
+                return Ok(Reservation::Locked(retry_after));
+            }
+
+            let failures = throttle.failures + 1;
+            let locked_until: Option<DateTime<Utc>> = sqlx::query_scalar(
+                r#"
+                UPDATE login_throttles SET
+                    failures = $3,
+                    last_failure_at = NOW(),
+                    locked_until = CASE WHEN $4 > 0 THEN NOW() + $4 * INTERVAL '1 second' END
+                WHERE scope = $1 AND key = $2
+                RETURNING locked_until
+                "#,
+            )
+            .bind(scope)
+            .bind(&key)
+            .bind(failures)
+            .bind(policy.delay(failures, threshold))
+            .fetch_one(&mut *tx)
+            .await?;
+
+            reserved.push(Reserved {
+                scope,
+                key,
+                locks: threshold > 0 && failures == threshold,
+                previous_locked_until: throttle.locked_until,
+                locked_until,
+            });
+        }
+        tx.commit().await?;
+
+        Ok(Reservation::Granted(LoginAttempt { reserved }))
+    }
+
+    /// 凭据验证通过，撤销预占时记下的失败
+    pub async fn release(pool: &PgPool, attempt: LoginAttempt) -> Result<(), sqlx::Error> {
+        for reserved in attempt.reserved {
+            // 只恢复本次预占设置的锁定时间，期间其他请求设置的锁定保持不变
+            sqlx::query(
+                r#"
+                UPDATE login_throttles SET
+                    failures = GREATEST(failures - 1, 0),
+                    locked_until = CASE
+                        WHEN locked_until IS NOT DISTINCT FROM $3 THEN $4
+                        ELSE locked_until
+                    END
+                WHERE scope = $1 AND key = $2
+                "#,
+            )
+            .bind(reserved.scope)
+            .bind(&reserved.key)
+            .bind(reserved.locked_until)
+            .bind(reserved.previous_locked_until)
+            .execute(pool)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn reset(pool: &PgPool, scope: &str, key: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM login_throttles WHERE scope = $1 AND key = $2")
+            .bind(scope)
+            .bind(key)
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY: LockoutPolicy = LockoutPolicy {
+        threshold: 5,
+        ip_threshold: 20,
+        duration: 900,
+    };
+
+    #[test]
+    fn first_failure_has_no_delay() {
+        assert_eq!(POLICY.delay(1, POLICY.threshold), 0);
+    }
+
+    #[test]
+    fn backs_off_exponentially_below_threshold() {
+        let delays: Vec<i64> = (2..5).map(|n| POLICY.delay(n, POLICY.threshold)).collect();
+        assert_eq!(delays, [2, 4, 8]);
+    }
+
+    #[test]
+    fn locks_for_full_duration_at_threshold() {
+        assert_eq!(POLICY.delay(5, POLICY.threshold), 900);
+        assert_eq!(POLICY.delay(6, POLICY.threshold), 900);
+    }
+
+    #[test]
+    fn backoff_is_capped_by_duration() {
+        assert_eq!(POLICY.delay(15, POLICY.ip_threshold), 900);
+        assert_eq!(POLICY.delay(i32::MAX, 0), 900);
+    }
+
+    #[test]
+    fn username_key_ignores_case_and_length() {
+        let key = LoginThrottleService::username_key(" Alice ");
+        assert_eq!(key, LoginThrottleService::username_key("alice"));
+        assert_eq!(
+            LoginThrottleService::username_key(&"a".repeat(1000)).len(),
+            64
+        );
+    }
+
+    #[test]
+    fn zero_threshold_disables_lockout() {
+        assert_eq!(POLICY.delay(3, 0), 4);
+    }
+}
@@ -0,0 +1,60 @@
+use chrono::{Duration, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// 每个挑战最多可提交的验证码次数，用尽后需重新输入密码
+pub const MAX_ATTEMPTS: i32 = 5;
+
+pub struct MfaChallengeService;
+
+impl MfaChallengeService {
+    /// 创建挑战，返回写入挑战令牌 `jti` 的 ID
+    pub async fn create(
+        pool: &PgPool,
+        user_id: Uuid,
+        expires_in: i64,
+    ) -> Result<Uuid, sqlx::Error> {
+        sqlx::query("DELETE FROM mfa_challenges WHERE expires_at < NOW()")
+            .execute(pool)
+            .await?;
+
+        sqlx::query_scalar(
+            "INSERT INTO mfa_challenges (user_id, expires_at) VALUES ($1, $2) RETURNING id",
+        )
+        .bind(user_id)
+        .bind(Utc::now() + Duration::seconds(expires_in))
+        .fetch_one(pool)
+        .await
+    }
+
+    /// 提交验证码前先占用一次尝试机会，挑战不存在、已过期或次数用尽时返回 false
+    pub async fn reserve_attempt(
+        pool: &PgPool,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE mfa_challenges SET attempts = attempts + 1
+            WHERE id = $1 AND user_id = $2 AND attempts < $3 AND expires_at > NOW()
+            "#,
+        )
+        .bind(id)
+        .bind(user_id)
+        .bind(MAX_ATTEMPTS)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 验证通过后删除挑战，使挑战令牌只能换取一次登录；已被删除时返回 false
+    pub async fn consume(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM mfa_challenges WHERE id = $1")
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
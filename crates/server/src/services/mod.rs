@@ -1,3 +1,35 @@
+pub mod api_key;
+pub mod audit;
+pub mod email_verification;
+pub mod group;
+pub mod identity;
+pub mod jwt_key;
+pub mod ldap;
+pub mod login_throttle;
+pub mod mfa_challenge;
+pub mod oidc;
+pub mod passkey;
+pub mod password_policy;
+pub mod password_reset;
+pub mod permission;
+pub mod setting;
+pub mod totp;
 pub mod user;
 
+pub use api_key::ApiKeyService;
+pub use audit::AuditService;
+pub use email_verification::EmailVerificationService;
+pub use group::GroupService;
+pub use identity::IdentityService;
+pub use jwt_key::JwtKeyService;
+pub use ldap::LdapService;
+pub use login_throttle::LoginThrottleService;
+pub use mfa_challenge::MfaChallengeService;
+pub use oidc::OidcService;
+pub use passkey::PasskeyService;
+pub use password_policy::PasswordPolicy;
+pub use password_reset::PasswordResetService;
+pub use permission::PermissionService;
+pub use setting::SettingService;
+pub use totp::TotpService;
 pub use user::UserService;
@@ -0,0 +1,313 @@
+use anyhow::{Result, anyhow};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use jsonwebtoken::{DecodingKey, Validation, jwk::JwkSet};
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use url::Url;
+
+use crate::{
+    config::{OidcProviderConfig, OidcProviderKind},
+    models::OidcState,
+    services::identity::ExternalIdentity,
+    utils::random_hex,
+};
+
+/// 授权请求的有效期（分钟）
+const STATE_TTL_MINUTES: i32 = 10;
+
+const GITHUB_AUTHORIZE_URL: &str = "https://github.com/login/oauth/authorize";
+const GITHUB_TOKEN_URL: &str = "https://github.com/login/oauth/access_token";
+const GITHUB_API_URL: &str = "https://api.github.com";
+
+#[derive(Debug, Deserialize)]
+struct DiscoveryDocument {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+    userinfo_endpoint: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    id_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubUser {
+    id: i64,
+    login: String,
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubEmail {
+    email: String,
+    primary: bool,
+    verified: bool,
+}
+
+pub struct OidcService;
+
+impl OidcService {
+    /// 创建授权请求并返回跳转到 IdP 的地址
+    pub async fn authorize(
+        pool: &PgPool,
+        http: &reqwest::Client,
+        provider: &OidcProviderConfig,
+    ) -> Result<String> {
+        let authorization_endpoint = match provider.kind {
+            OidcProviderKind::Oidc => Self::discover(http, provider).await?.authorization_endpoint,
+            OidcProviderKind::Github => GITHUB_AUTHORIZE_URL.to_string(),
+        };
+
+        let state = OidcState::new(
+            random_hex(16),
+            provider.name.clone(),
+            random_hex(32),
+            random_hex(16),
+        );
+
+        sqlx::query(
+            "INSERT INTO oidc_states (state, provider, code_verifier, nonce) VALUES ($1, $2, $3, $4)",
+        )
+        .bind(&state.state)
+        .bind(&state.provider)
+        .bind(&state.code_verifier)
+        .bind(&state.nonce)
+        .execute(pool)
+        .await?;
+
+        let code_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(state.code_verifier.as_bytes()));
+        let mut url = Url::parse(&authorization_endpoint)?;
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &provider.client_id)
+            .append_pair("redirect_uri", &provider.redirect_uri)
+            .append_pair("scope", &provider.scopes)
+            .append_pair("state", &state.state)
+            .append_pair("nonce", &state.nonce)
+            .append_pair("code_challenge", &code_challenge)
+            .append_pair("code_challenge_method", "S256");
+
+        Ok(url.into())
+    }
+
+    /// 取出并删除授权请求，过期或提供方不匹配时返回 `None`
+    pub async fn take_state(
+        pool: &PgPool,
+        provider: &str,
+        state: &str,
+    ) -> Result<Option<OidcState>, sqlx::Error> {
+        sqlx::query("DELETE FROM oidc_states WHERE created_at < NOW() - make_interval(mins => $1)")
+            .bind(STATE_TTL_MINUTES)
+            .execute(pool)
+            .await?;
+
+        sqlx::query_as("DELETE FROM oidc_states WHERE state = $1 AND provider = $2 RETURNING *")
+            .bind(state)
+            .bind(provider)
+            .fetch_optional(pool)
+            .await
+    }
+
+    /// 用授权码换取令牌并解析出外部身份
+    pub async fn exchange(
+        http: &reqwest::Client,
+        provider: &OidcProviderConfig,
+        state: &OidcState,
+        code: &str,
+    ) -> Result<ExternalIdentity> {
+        match provider.kind {
+            OidcProviderKind::Oidc => Self::exchange_oidc(http, provider, state, code).await,
+            OidcProviderKind::Github => Self::exchange_github(http, provider, state, code).await,
+        }
+    }
+
+    async fn discover(
+        http: &reqwest::Client,
+        provider: &OidcProviderConfig,
+    ) -> Result<DiscoveryDocument> {
+        let url = format!("{}/.well-known/openid-configuration", provider.issuer);
+        let document: DiscoveryDocument = http
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        if document.issuer.trim_end_matches('/') != provider.issuer {
+            return Err(anyhow!(
+                "discovery 文档的 issuer 与配置不一致: {}",
+                document.issuer
+            ));
+        }
+
+        Ok(document)
+    }
+
+    async fn exchange_oidc(
+        http: &reqwest::Client,
+        provider: &OidcProviderConfig,
+        state: &OidcState,
+        code: &str,
+    ) -> Result<ExternalIdentity> {
+        let discovery = Self::discover(http, provider).await?;
+        let token =
+            Self::request_token(http, provider, &discovery.token_endpoint, state, code).await?;
+        let id_token = token
+            .id_token
+            .ok_or_else(|| anyhow!("令牌响应缺少 id_token"))?;
+
+        let header = jsonwebtoken::decode_header(&id_token)?;
+        let jwks: JwkSet = http
+            .get(&discovery.jwks_uri)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let jwk = match &header.kid {
+            Some(kid) => jwks.find(kid),
+            None => jwks.keys.first(),
+        }
+        .ok_or_else(|| anyhow!("找不到 id_token 对应的签名密钥"))?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_audience(&[&provider.client_id]);
+        validation.set_issuer(&[&discovery.issuer]);
+        let mut claims =
+            jsonwebtoken::decode::<Value>(&id_token, &DecodingKey::from_jwk(jwk)?, &validation)?
+                .claims;
+
+        if claims.get("nonce").and_then(Value::as_str) != Some(state.nonce.as_str()) {
+            return Err(anyhow!("id_token 的 nonce 不匹配"));
+        }
+
+        // 部分 IdP 不在 id_token 中携带 email，需要从 userinfo 补全
+        if claims.get("email").is_none()
+            && let Some(userinfo_endpoint) = &discovery.userinfo_endpoint
+        {
+            let userinfo: Value = http
+                .get(userinfo_endpoint)
+                .bearer_auth(&token.access_token)
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+
+            if userinfo.get("sub") == claims.get("sub")
+                && let (Some(claims), Value::Object(userinfo)) = (claims.as_object_mut(), userinfo)
+            {
+                for (key, value) in userinfo {
+                    claims.entry(key).or_insert(value);
+                }
+            }
+        }
+
+        let string_claim = |key: &str| claims.get(key).and_then(Value::as_str).map(str::to_string);
+
+        Ok(ExternalIdentity {
+            subject: string_claim("sub").ok_or_else(|| anyhow!("id_token 缺少 sub"))?,
+            email: string_claim("email"),
+            email_verified: match claims.get("email_verified") {
+                Some(Value::Bool(v)) => *v,
+                Some(Value::String(v)) => v == "true",
+                _ => false,
+            },
+            preferred_username: string_claim("preferred_username"),
+            name: string_claim("name"),
+            groups: Self::groups_claim(&claims, &provider.groups_claim),
+        })
+    }
+
+    async fn exchange_github(
+        http: &reqwest::Client,
+        provider: &OidcProviderConfig,
+        state: &OidcState,
+        code: &str,
+    ) -> Result<ExternalIdentity> {
+        let token = Self::request_token(http, provider, GITHUB_TOKEN_URL, state, code).await?;
+
+        let user: GithubUser = http
+            .get(format!("{}/user", GITHUB_API_URL))
+            .bearer_auth(&token.access_token)
+            .header("User-Agent", "rikkahub-server")
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let emails: Vec<GithubEmail> = http
+            .get(format!("{}/user/emails", GITHUB_API_URL))
+            .bearer_auth(&token.access_token)
+            .header("User-Agent", "rikkahub-server")
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let email = emails.into_iter().find(|e| e.primary && e.verified);
+
+        Ok(ExternalIdentity {
+            subject: user.id.to_string(),
+            email_verified: email.is_some(),
+            email: email.map(|e| e.email),
+            preferred_username: Some(user.login),
+            name: user.name,
+            groups: Vec::new(),
+        })
+    }
+
+    async fn request_token(
+        http: &reqwest::Client,
+        provider: &OidcProviderConfig,
+        token_endpoint: &str,
+        state: &OidcState,
+        code: &str,
+    ) -> Result<TokenResponse> {
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", provider.redirect_uri.as_str()),
+            ("client_id", provider.client_id.as_str()),
+            ("code_verifier", state.code_verifier.as_str()),
+        ];
+        if !provider.client_secret.is_empty() {
+            form.push(("client_secret", provider.client_secret.as_str()));
+        }
+
+        let token = http
+            .post(token_endpoint)
+            .header("Accept", "application/json")
+            .form(&form)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(token)
+    }
+
+    /// 支持 `realm_access.roles` 这类以点分隔的嵌套 claim
+    fn groups_claim(claims: &Value, path: &str) -> Vec<String> {
+        path.split('.')
+            .try_fold(claims, |value, key| value.get(key))
+            .and_then(Value::as_array)
+            .map(|groups| {
+                groups
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,119 @@
+use anyhow::Result;
+use sqlx::PgPool;
+use uuid::Uuid;
+use webauthn_rs::{Webauthn, WebauthnBuilder, prelude::Url};
+
+use crate::{
+    config::WebauthnConfig,
+    models::{Passkey, WebauthnChallenge},
+};
+
+/// 注册与认证流程的有效期（分钟）
+const CHALLENGE_TTL_MINUTES: i32 = 5;
+
+pub mod challenge_kind {
+    pub const REGISTER: &str = "register";
+    pub const DISCOVERABLE: &str = "discoverable";
+}
+
+pub struct PasskeyService;
+
+impl PasskeyService {
+    pub fn webauthn(config: &WebauthnConfig) -> Result<Webauthn> {
+        let origin = Url::parse(&config.rp_origin)?;
+        let webauthn = WebauthnBuilder::new(&config.rp_id, &origin)?
+            .rp_name(&config.rp_name)
+            .build()?;
+        Ok(webauthn)
+    }
+
+    pub async fn list_by_user(pool: &PgPool, user_id: Uuid) -> Result<Vec<Passkey>, sqlx::Error> {
+        sqlx::query_as("SELECT * FROM passkeys WHERE user_id = $1 ORDER BY created_at")
+            .bind(user_id)
+            .fetch_all(pool)
+            .await
+    }
+
+    pub async fn find_by_credential_id(
+        pool: &PgPool,
+        credential_id: &[u8],
+    ) -> Result<Option<Passkey>, sqlx::Error> {
+        sqlx::query_as("SELECT * FROM passkeys WHERE credential_id = $1")
+            .bind(credential_id)
+            .fetch_optional(pool)
+            .await
+    }
+
+    pub async fn create(pool: &PgPool, passkey: &Passkey) -> Result<Passkey, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            INSERT INTO passkeys (id, user_id, name, credential_id, credential)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(passkey.id)
+        .bind(passkey.user_id)
+        .bind(&passkey.name)
+        .bind(&passkey.credential_id)
+        .bind(&passkey.credential)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// 记录使用时间，并保存更新后的签名计数等凭证信息
+    pub async fn record_use(pool: &PgPool, passkey: &Passkey) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE passkeys SET credential = $2, last_used_at = NOW() WHERE id = $1")
+            .bind(passkey.id)
+            .bind(&passkey.credential)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete(pool: &PgPool, user_id: Uuid, id: Uuid) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM passkeys WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn save_challenge(
+        pool: &PgPool,
+        challenge: &WebauthnChallenge,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO webauthn_challenges (id, user_id, kind, state) VALUES ($1, $2, $3, $4)",
+        )
+        .bind(challenge.id)
+        .bind(challenge.user_id)
+        .bind(&challenge.kind)
+        .bind(&challenge.state)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 取出并删除流程状态，每个 challenge 只能完成一次
+    pub async fn take_challenge(
+        pool: &PgPool,
+        id: Uuid,
+    ) -> Result<Option<WebauthnChallenge>, sqlx::Error> {
+        sqlx::query(
+            "DELETE FROM webauthn_challenges WHERE created_at < NOW() - make_interval(mins => $1)",
+        )
+        .bind(CHALLENGE_TTL_MINUTES)
+        .execute(pool)
+        .await?;
+
+        sqlx::query_as("DELETE FROM webauthn_challenges WHERE id = $1 RETURNING *")
+            .bind(id)
+            .fetch_optional(pool)
+            .await
+    }
+}
@@ -0,0 +1,173 @@
+use std::{collections::HashSet, sync::OnceLock};
+
+use sqlx::PgPool;
+
+use crate::{
+    i18n::Message,
+    services::{SettingService, setting::keys},
+};
+
+/// 密码最大长度（字符）
+const MAX_LENGTH: usize = 128;
+
+/// 随程序分发的常见弱密码列表
+const COMMON_PASSWORDS: &str = include_str!("../../data/common-passwords.txt");
+
+fn common_passwords() -> &'static HashSet<String> {
+    static SET: OnceLock<HashSet<String>> = OnceLock::new();
+    SET.get_or_init(|| {
+        COMMON_PASSWORDS
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_lowercase)
+            .collect()
+    })
+}
+
+/// 密码强度策略，取值来自系统设置
+#[derive(Debug, Clone, Copy)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    /// 至少包含几类字符（小写字母、大写字母、数字、符号）
+    pub min_char_classes: usize,
+    /// 是否拒绝随程序分发的常见弱密码，以及与用户名相同的密码
+    pub reject_common: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            min_char_classes: 2,
+            reject_common: true,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    pub async fn load(pool: &PgPool) -> Result<Self, sqlx::Error> {
+        let default = Self::default();
+
+        Ok(Self {
+            min_length: SettingService::get_or(
+                pool,
+                keys::AUTH_PASSWORD_MIN_LENGTH,
+                default.min_length,
+            )
+            .await?,
+            min_char_classes: SettingService::get_or(
+                pool,
+                keys::AUTH_PASSWORD_MIN_CHAR_CLASSES,
+                default.min_char_classes,
+            )
+            .await?,
+            reject_common: SettingService::get_or(
+                pool,
+                keys::AUTH_PASSWORD_REJECT_COMMON,
+                default.reject_common,
+            )
+            .await?,
+        })
+    }
+
+    /// 校验密码，不符合时返回面向用户的错误信息
+    pub fn validate(&self, password: &str, username: &str) -> Result<(), Message> {
+        let length = password.chars().count();
+        if length < self.min_length {
+            return Err(Message::new("password.too_short").arg("min", self.min_length));
+        }
+
+        if length > MAX_LENGTH {
+            return Err(Message::new("password.too_long").arg("max", MAX_LENGTH));
+        }
+
+        let classes = [
+            password.chars().any(|c| c.is_lowercase()),
+            password.chars().any(|c| c.is_uppercase()),
+            password.chars().any(|c| c.is_ascii_digit()),
+            password.chars().any(|c| !c.is_alphanumeric()),
+        ]
+        .into_iter()
+        .filter(|&present| present)
+        .count();
+
+        if classes < self.min_char_classes {
+            return Err(
+                Message::new("password.too_few_char_classes").arg("min", self.min_char_classes)
+            );
+        }
+
+        if self.reject_common {
+            let lower = password.to_lowercase();
+            if lower == username.to_lowercase() || common_passwords().contains(&lower) {
+                return Err(Message::new("password.too_common"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(result: Result<(), Message>) -> Option<&'static str> {
+        result.err().map(|msg| msg.key())
+    }
+
+    #[test]
+    fn accepts_strong_password() {
+        let policy = PasswordPolicy::default();
+        assert_eq!(key(policy.validate("Tr0ub4dor&3", "alice")), None);
+    }
+
+    #[test]
+    fn enforces_length_limits() {
+        let policy = PasswordPolicy::default();
+        assert_eq!(
+            key(policy.validate("aB3$", "alice")),
+            Some("password.too_short")
+        );
+        // 按字符而不是字节计算长度
+        assert_eq!(key(policy.validate("密码密码密码a1", "alice")), None);
+        assert_eq!(key(policy.validate(&"密码a1".repeat(32), "alice")), None);
+        assert_eq!(
+            key(policy.validate(&"aB3$".repeat(33), "alice")),
+            Some("password.too_long")
+        );
+    }
+
+    #[test]
+    fn requires_char_classes() {
+        let policy = PasswordPolicy {
+            min_char_classes: 3,
+            ..PasswordPolicy::default()
+        };
+        assert_eq!(
+            key(policy.validate("abcdefgh12", "alice")),
+            Some("password.too_few_char_classes")
+        );
+        assert_eq!(key(policy.validate("abcdefGH12", "alice")), None);
+    }
+
+    #[test]
+    fn rejects_common_and_username() {
+        let policy = PasswordPolicy::default();
+        assert_eq!(
+            key(policy.validate("Password1", "alice")),
+            Some("password.too_common")
+        );
+        assert_eq!(
+            key(policy.validate("Alice.Smith", "alice.smith")),
+            Some("password.too_common")
+        );
+
+        let lenient = PasswordPolicy {
+            reject_common: false,
+            ..PasswordPolicy::default()
+        };
+        assert_eq!(key(lenient.validate("Password1", "alice")), None);
+    }
+}
@@ -0,0 +1,98 @@
+use chrono::{Duration, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{models::PasswordResetToken, utils::random_hex};
+
+/// 重置令牌有效期（秒）
+pub const TOKEN_EXPIRES_IN: i64 = 1800;
+/// 同一用户两次发送重置邮件的最短间隔（秒）
+const RESEND_INTERVAL: i64 = 60;
+
+pub struct PasswordResetService;
+
+impl PasswordResetService {
+    fn hash_token(token: &str) -> String {
+        hex::encode(Sha256::digest(token.as_bytes()))
+    }
+
+    /// 为用户生成新的重置令牌，距上次生成不足间隔时返回 `None`
+    pub async fn create(pool: &PgPool, user_id: Uuid) -> Result<Option<String>, sqlx::Error> {
+        let (recent,): (bool,) = sqlx::query_as(
+            "SELECT EXISTS(SELECT 1 FROM password_reset_tokens WHERE user_id = $1 AND created_at > $2)",
+        )
+        .bind(user_id)
+        .bind(Utc::now() - Duration::seconds(RESEND_INTERVAL))
+        .fetch_one(pool)
+        .await?;
+
+        if recent {
+            return Ok(None);
+        }
+
+        let token = random_hex(32);
+        let record = PasswordResetToken::new(
+            user_id,
+            Self::hash_token(&token),
+            Utc::now() + Duration::seconds(TOKEN_EXPIRES_IN),
+        );
+
+        sqlx::query(
+            r#"
+            INSERT INTO password_reset_tokens (id, user_id, token_hash, expires_at, created_at)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+        )
+        .bind(record.id)
+        .bind(record.user_id)
+        .bind(&record.token_hash)
+        .bind(record.expires_at)
+        .bind(record.created_at)
+        .execute(pool)
+        .await?;
+
+        Ok(Some(token))
+    }
+
+    /// 查找未使用且未过期的令牌
+    pub async fn find_valid(
+        pool: &PgPool,
+        token: &str,
+    ) -> Result<Option<PasswordResetToken>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT * FROM password_reset_tokens
+            WHERE token_hash = $1 AND used_at IS NULL AND expires_at > NOW()
+            "#,
+        )
+        .bind(Self::hash_token(token))
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// 标记令牌已使用并作废该用户的其他令牌，令牌已被使用时返回 false
+    pub async fn consume(pool: &PgPool, record: &PasswordResetToken) -> Result<bool, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+
+        let result = sqlx::query(
+            "UPDATE password_reset_tokens SET used_at = NOW() WHERE id = $1 AND used_at IS NULL",
+        )
+        .bind(record.id)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query("DELETE FROM password_reset_tokens WHERE user_id = $1 AND id <> $2")
+            .bind(record.user_id)
+            .bind(record.id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,94 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub struct PermissionService;
+
+impl PermissionService {
+    /// 获取用户通过所在用户组获得的全部权限
+    pub async fn list_user_permissions(
+        pool: &PgPool,
+        user_id: Uuid,
+    ) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+            SELECT DISTINCT gp.permission
+            FROM group_permissions gp
+            JOIN user_groups ug ON ug.group_id = gp.group_id
+            JOIN groups g ON g.id = gp.group_id
+            WHERE ug.user_id = $1
+              AND gp.deleted_at IS NULL
+              AND g.deleted_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn user_has_permission(
+        pool: &PgPool,
+        user_id: Uuid,
+        permission: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let granted = Self::list_user_permissions(pool, user_id).await?;
+        Ok(Self::is_granted(&granted, permission))
+    }
+
+    /// 判断权限列表是否覆盖所需权限
+    pub fn is_granted(granted: &[String], permission: &str) -> bool {
+        granted.iter().any(|g| Self::matches(g, permission))
+    }
+
+    /// 支持 `*` 以及 `chat.*` 形式的通配
+    pub fn matches(granted: &str, permission: &str) -> bool {
+        if granted == "*" || granted == permission {
+            return true;
+        }
+
+        match granted.strip_suffix('*') {
+            Some(prefix) if prefix.ends_with('.') => permission.starts_with(prefix),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_and_global_wildcard() {
+        assert!(PermissionService::matches("chat.read", "chat.read"));
+        assert!(PermissionService::matches("*", "admin.users.delete"));
+        assert!(!PermissionService::matches("chat.read", "chat.write"));
+    }
+
+    #[test]
+    fn prefix_wildcard_matches_descendants() {
+        assert!(PermissionService::matches("chat.*", "chat.read"));
+        assert!(PermissionService::matches("admin.*", "admin.users.delete"));
+        assert!(!PermissionService::matches("chat.*", "chat"));
+        assert!(!PermissionService::matches("chat.*", "chatroom.read"));
+    }
+
+    #[test]
+    fn wildcard_only_at_segment_boundary() {
+        assert!(!PermissionService::matches("chat*", "chatroom.read"));
+        assert!(!PermissionService::matches("chat.r*", "chat.read"));
+        assert!(!PermissionService::matches("*.read", "chat.read"));
+    }
+
+    #[test]
+    fn is_granted_checks_any() {
+        let granted = vec!["chat.read".to_string(), "admin.audit.*".to_string()];
+        assert!(PermissionService::is_granted(
+            &granted,
+            "admin.audit.export"
+        ));
+        assert!(!PermissionService::is_granted(
+            &granted,
+            "admin.settings.update"
+        ));
+        assert!(!PermissionService::is_granted(&[], "chat.read"));
+    }
+}
@@ -0,0 +1,97 @@
+use serde::de::DeserializeOwned;
+use serde_json::Value as JsonValue;
+use sqlx::PgPool;
+
+use crate::models::{Setting, SettingType};
+
+/// 系统设置的键
+pub mod keys {
+    /// 要求成员必须启用两步验证的用户组名称列表
+    pub const AUTH_REQUIRE_2FA_GROUPS: &str = "auth.require_2fa_groups";
+    /// 同一用户名连续登录失败多少次后临时锁定
+    pub const AUTH_LOCKOUT_THRESHOLD: &str = "auth.lockout_threshold";
+    /// 同一 IP 连续登录失败多少次后临时锁定
+    pub const AUTH_LOCKOUT_IP_THRESHOLD: &str = "auth.lockout_ip_threshold";
+    /// 临时锁定时长（秒）
+    pub const AUTH_LOCKOUT_DURATION: &str = "auth.lockout_duration";
+    /// 是否要求用户验证邮箱后才能使用对话功能
+    pub const AUTH_REQUIRE_VERIFIED_EMAIL: &str = "auth.require_verified_email";
+    /// 密码最短长度
+    pub const AUTH_PASSWORD_MIN_LENGTH: &str = "auth.password_min_length";
+    /// 密码至少包含的字符类别数
+    pub const AUTH_PASSWORD_MIN_CHAR_CLASSES: &str = "auth.password_min_char_classes";
+    /// 是否拒绝常见弱密码
+    pub const AUTH_PASSWORD_REJECT_COMMON: &str = "auth.password_reject_common";
+}
+
+pub struct SettingService;
+
+impl SettingService {
+    pub async fn list(pool: &PgPool) -> Result<Vec<Setting>, sqlx::Error> {
+        sqlx::query_as("SELECT * FROM settings ORDER BY key")
+            .fetch_all(pool)
+            .await
+    }
+
+    pub async fn find(pool: &PgPool, key: &str) -> Result<Option<Setting>, sqlx::Error> {
+        sqlx::query_as("SELECT * FROM settings WHERE key = $1")
+            .bind(key)
+            .fetch_optional(pool)
+            .await
+    }
+
+    /// 读取设置值，不存在或类型不匹配时返回默认值
+    pub async fn get_or<T: DeserializeOwned>(
+        pool: &PgPool,
+        key: &str,
+        default: T,
+    ) -> Result<T, sqlx::Error> {
+        let setting = Self::find(pool, key).await?;
+
+        Ok(setting
+            .and_then(|s| match serde_json::from_value(s.value) {
+                Ok(v) => Some(v),
+                Err(e) => {
+                    tracing::warn!("设置 {} 的值无效，使用默认值: {}", key, e);
+                    None
+                }
+            })
+            .unwrap_or(default))
+    }
+
+    pub async fn set(
+        pool: &PgPool,
+        key: &str,
+        value: &JsonValue,
+        setting_type: SettingType,
+        description: Option<&str>,
+    ) -> Result<Setting, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            INSERT INTO settings (key, value, type, description)
+            VALUES ($1, $2, $3, COALESCE($4, ''))
+            ON CONFLICT (key) DO UPDATE SET
+                value = EXCLUDED.value,
+                type = EXCLUDED.type,
+                description = COALESCE($4, settings.description),
+                updated_at = NOW()
+            RETURNING *
+            "#,
+        )
+        .bind(key)
+        .bind(value)
+        .bind(setting_type.as_str())
+        .bind(description)
+        .fetch_one(pool)
+        .await
+    }
+
+    pub async fn delete(pool: &PgPool, key: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM settings WHERE key = $1")
+            .bind(key)
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
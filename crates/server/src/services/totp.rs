@@ -0,0 +1,216 @@
+use std::collections::HashSet;
+
+use anyhow::{Result, anyhow};
+use chrono::Utc;
+use sqlx::PgPool;
+use totp_rs::{Algorithm, Secret, TOTP};
+use uuid::Uuid;
+
+use crate::{
+    models::{RecoveryCode, User, UserTotp},
+    services::{GroupService, SettingService, setting::keys::AUTH_REQUIRE_2FA_GROUPS},
+    utils::{constant_time_eq, hash_password, random_bytes, random_hex, verify_password},
+};
+
+const ISSUER: &str = "RikkaHub";
+const STEP_SECONDS: u64 = 30;
+const RECOVERY_CODE_COUNT: usize = 10;
+
+pub struct TotpService;
+
+impl TotpService {
+    pub async fn find(pool: &PgPool, user_id: Uuid) -> Result<Option<UserTotp>, sqlx::Error> {
+        sqlx::query_as("SELECT * FROM user_totp WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await
+    }
+
+    pub async fn find_enabled(
+        pool: &PgPool,
+        user_id: Uuid,
+    ) -> Result<Option<UserTotp>, sqlx::Error> {
+        Ok(Self::find(pool, user_id)
+            .await?
+            .filter(UserTotp::is_enabled))
+    }
+
+    /// 生成新的待确认密钥，返回 (base32 密钥, otpauth URI)
+    pub async fn begin_enrollment(pool: &PgPool, user: &User) -> Result<(String, String)> {
+        let secret = Secret::Raw(random_bytes(20)).to_encoded().to_string();
+        let totp = UserTotp::new(user.id, secret);
+
+        sqlx::query(
+            r#"
+            INSERT INTO user_totp (user_id, secret)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id) DO UPDATE SET
+                secret = EXCLUDED.secret,
+                last_used_step = 0,
+                enabled_at = NULL,
+                created_at = NOW()
+            "#,
+        )
+        .bind(totp.user_id)
+        .bind(&totp.secret)
+        .execute(pool)
+        .await?;
+
+        let uri = Self::build(&totp.secret, &user.username)?.get_url();
+        Ok((totp.secret, uri))
+    }
+
+    /// 校验验证码，同一时间步的验证码只能使用一次
+    pub async fn verify_code(pool: &PgPool, totp: &UserTotp, code: &str) -> Result<bool> {
+        let code = code.trim();
+        let generator = Self::build(&totp.secret, "")?;
+        let current = Utc::now().timestamp() as u64 / STEP_SECONDS;
+
+        let Some(step) = [current - 1, current, current + 1]
+            .into_iter()
+            .find(|step| {
+                constant_time_eq(
+                    generator.generate(step * STEP_SECONDS).as_bytes(),
+                    code.as_bytes(),
+                )
+            })
+        else {
+            return Ok(false);
+        };
+
+        let result = sqlx::query(
+            "UPDATE user_totp SET last_used_step = $2 WHERE user_id = $1 AND last_used_step < $2",
+        )
+        .bind(totp.user_id)
+        .bind(step as i64)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn enable(pool: &PgPool, user_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE user_totp SET enabled_at = NOW() WHERE user_id = $1")
+            .bind(user_id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn disable(pool: &PgPool, user_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM user_recovery_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(pool)
+            .await?;
+
+        sqlx::query("DELETE FROM user_totp WHERE user_id = $1")
+            .bind(user_id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// 重新生成恢复码，旧的恢复码全部作废
+    pub async fn regenerate_recovery_codes(pool: &PgPool, user_id: Uuid) -> Result<Vec<String>> {
+        let codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+            .map(|_| {
+                let raw = random_hex(5);
+                format!("{}-{}", &raw[..5], &raw[5..])
+            })
+            .collect();
+
+        let mut tx = pool.begin().await?;
+        sqlx::query("DELETE FROM user_recovery_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        for code in &codes {
+            let recovery_code = RecoveryCode::new(user_id, hash_password(&Self::normalize(code))?);
+            sqlx::query(
+                "INSERT INTO user_recovery_codes (id, user_id, code_hash) VALUES ($1, $2, $3)",
+            )
+            .bind(recovery_code.id)
+            .bind(recovery_code.user_id)
+            .bind(&recovery_code.code_hash)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(codes)
+    }
+
+    /// 使用恢复码，成功后该恢复码立即失效
+    pub async fn use_recovery_code(pool: &PgPool, user_id: Uuid, code: &str) -> Result<bool> {
+        let code = Self::normalize(code);
+        let candidates: Vec<RecoveryCode> = sqlx::query_as(
+            "SELECT * FROM user_recovery_codes WHERE user_id = $1 AND used_at IS NULL",
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+        for candidate in candidates {
+            if verify_password(&code, &candidate.code_hash)? {
+                let result = sqlx::query(
+                    "UPDATE user_recovery_codes SET used_at = NOW() WHERE id = $1 AND used_at IS NULL",
+                )
+                .bind(candidate.id)
+                .execute(pool)
+                .await?;
+
+                return Ok(result.rows_affected() > 0);
+            }
+        }
+
+        Ok(false)
+    }
+
+    pub async fn count_recovery_codes(pool: &PgPool, user_id: Uuid) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT COUNT(*) FROM user_recovery_codes WHERE user_id = $1 AND used_at IS NULL",
+        )
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// 用户是否属于要求启用两步验证的用户组
+    pub async fn is_required(pool: &PgPool, user_id: Uuid) -> Result<bool, sqlx::Error> {
+        let required: HashSet<String> =
+            SettingService::get_or(pool, AUTH_REQUIRE_2FA_GROUPS, HashSet::new()).await?;
+        if required.is_empty() {
+            return Ok(false);
+        }
+
+        let groups = GroupService::list_by_user(pool, user_id).await?;
+        Ok(groups.iter().any(|g| required.contains(&g.name)))
+    }
+
+    fn build(secret: &str, account: &str) -> Result<TOTP> {
+        let secret = Secret::Encoded(secret.to_string())
+            .to_bytes()
+            .map_err(|e| anyhow!("TOTP 密钥无效: {:?}", e))?;
+
+        TOTP::new(
+            Algorithm::SHA1,
+            6,
+            1,
+            STEP_SECONDS,
+            secret,
+            Some(ISSUER.to_string()),
+            account.replace(':', "_"),
+        )
+        .map_err(|e| anyhow!("创建 TOTP 失败: {}", e))
+    }
+
+    fn normalize(code: &str) -> String {
+        code.chars()
+            .filter(char::is_ascii_alphanumeric)
+            .collect::<String>()
+            .to_ascii_lowercase()
+    }
+}
@@ -22,4 +22,120 @@ impl UserService {
             .fetch_optional(pool)
             .await
     }
+
+    pub async fn find_by_email(pool: &PgPool, email: &str) -> Result<Option<User>, sqlx::Error> {
+        sqlx::query_as("SELECT * FROM users WHERE LOWER(email) = LOWER($1) AND deleted_at IS NULL")
+            .bind(email)
+            .fetch_optional(pool)
+            .await
+    }
+
+    /// 用户名是否已被占用（包括已删除的用户，避免唯一约束冲突）
+    pub async fn username_exists(pool: &PgPool, username: &str) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM users WHERE username = $1)")
+            .bind(username)
+            .fetch_one(pool)
+            .await
+    }
+
+    pub async fn create(pool: &PgPool, user: &User) -> Result<User, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            INSERT INTO users (
+                id, username, email, email_verified_at, password_hash, nickname, avatar, status,
+                must_change_password
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING *
+            "#,
+        )
+        .bind(user.id)
+        .bind(&user.username)
+        .bind(&user.email)
+        .bind(user.email_verified_at)
+        .bind(&user.password_hash)
+        .bind(&user.nickname)
+        .bind(&user.avatar)
+        .bind(user.status)
+        .bind(user.must_change_password)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// 更新密码并清除强制修改标记，此前签发的令牌随之失效
+    pub async fn update_password(
+        pool: &PgPool,
+        user_id: Uuid,
+        password_hash: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET password_hash = $2,
+                must_change_password = FALSE,
+                password_changed_at = NOW(),
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(user_id)
+        .bind(password_hash)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 写入已验证的邮箱地址
+    pub async fn set_verified_email(
+        pool: &PgPool,
+        user_id: Uuid,
+        email: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET email = $2, email_verified_at = NOW(), updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(user_id)
+        .bind(email)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn set_locale(
+        pool: &PgPool,
+        user_id: Uuid,
+        locale: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE users SET locale = $2, updated_at = NOW() WHERE id = $1")
+            .bind(user_id)
+            .bind(locale)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// 用新参数重新哈希同一密码，不影响已签发的令牌；
+    /// 期间密码已被修改时不做更新
+    pub async fn rehash_password(
+        pool: &PgPool,
+        user_id: Uuid,
+        old_hash: &str,
+        new_hash: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE users SET password_hash = $3 WHERE id = $1 AND password_hash = $2")
+            .bind(user_id)
+            .bind(old_hash)
+            .bind(new_hash)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
 }
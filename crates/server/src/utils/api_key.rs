@@ -0,0 +1,25 @@
+use sha2::{Digest, Sha256};
+
+use super::random_hex;
+
+/// API Key 前缀，用于在认证时与 JWT 区分
+pub const API_KEY_PREFIX: &str = "rk-";
+
+/// 列表中展示的前缀长度（包含 `rk-`）
+const DISPLAY_PREFIX_LEN: usize = 11;
+
+/// 生成新的 API Key，返回 (完整 key, 展示用前缀)
+pub fn generate_api_key() -> (String, String) {
+    let key = format!("{}{}", API_KEY_PREFIX, random_hex(32));
+    let prefix = key[..DISPLAY_PREFIX_LEN].to_string();
+    (key, prefix)
+}
+
+/// API Key 本身是高熵随机值，使用 SHA-256 存储即可支持按哈希直接查找
+pub fn hash_api_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+pub fn is_api_key(token: &str) -> bool {
+    token.starts_with(API_KEY_PREFIX)
+}
@@ -1,41 +1,285 @@
-use anyhow::{anyhow, Result};
-use chrono::{Duration, Utc};
-use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{
+    Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, decode_header, encode,
+    jwk::{Jwk, JwkSet, KeyAlgorithm},
+};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+/// 受限令牌的用途，普通登录令牌不携带该字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenPurpose {
+    /// 密码已验证，等待提交两步验证码
+    MfaChallenge,
+    /// 所在用户组要求两步验证，只能用于绑定 TOTP
+    MfaEnrollment,
+}
+
+/// 代为操作的主体（RFC 8693 `act` claim），用于管理员模拟登录
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Actor {
+    pub sub: Uuid,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: Uuid,
     pub exp: usize,
     pub iat: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purpose: Option<TokenPurpose>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub act: Option<Actor>,
+    /// 两步验证挑战令牌对应的挑战 ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<Uuid>,
+}
+
+/// 非对称签名私钥
+pub struct SigningKey {
+    pub kid: String,
+    pub algorithm: Algorithm,
+    pub key: EncodingKey,
+}
+
+/// 某一时刻可用的全部密钥
+pub struct KeySet {
+    /// 为 `None` 时使用共享密钥（HS256）签名
+    signing: Option<SigningKey>,
+    verifying: HashMap<String, (Algorithm, DecodingKey)>,
+    /// 不带 kid 的 HS256 令牌使用的共享密钥
+    secret: Option<(EncodingKey, DecodingKey)>,
+    /// 共享密钥仅用于验证旧令牌时，只接受此前签发的令牌
+    secret_issued_before: Option<DateTime<Utc>>,
+    jwks: JwkSet,
+}
+
+impl KeySet {
+    /// `public_keys` 为仍可用于验证的公钥，需带有 kid 和 alg
+    pub fn new(signing: Option<SigningKey>, public_keys: Vec<Jwk>, secret: &str) -> Result<Self> {
+        let secret = (!secret.is_empty()).then(|| {
+            (
+                EncodingKey::from_secret(secret.as_bytes()),
+                DecodingKey::from_secret(secret.as_bytes()),
+            )
+        });
+
+        if signing.is_none() && secret.is_none() {
+            return Err(anyhow!("未配置 JWT 签名密钥"));
+        }
+
+        let mut verifying = HashMap::new();
+        for jwk in &public_keys {
+            let kid = jwk
+                .common
+                .key_id
+                .clone()
+                .ok_or_else(|| anyhow!("JWK 缺少 kid"))?;
+            let algorithm = match jwk.common.key_algorithm {
+                Some(KeyAlgorithm::EdDSA) => Algorithm::EdDSA,
+                Some(KeyAlgorithm::RS256) => Algorithm::RS256,
+                _ => return Err(anyhow!("JWK {} 的算法不受支持", kid)),
+            };
+            let key =
+                DecodingKey::from_jwk(jwk).map_err(|e| anyhow!("解析 JWK {} 失败: {}", kid, e))?;
+            verifying.insert(kid, (algorithm, key));
+        }
+
+        Ok(Self {
+            signing,
+            verifying,
+            secret,
+            secret_issued_before: None,
+            jwks: JwkSet { keys: public_keys },
+        })
+    }
+
+    /// 切换到非对称算法后，共享密钥只用于验证 `issued_before` 之前签发的令牌
+    pub fn restrict_secret(mut self, issued_before: DateTime<Utc>) -> Self {
+        self.secret_issued_before = Some(issued_before);
+        self
+    }
+}
+
+/// 运行期共享的 JWT 密钥，轮换后原地替换
+#[derive(Clone)]
+pub struct JwtKeys {
+    inner: Arc<RwLock<KeySet>>,
+}
+
+impl std::fmt::Debug for JwtKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JwtKeys").finish_non_exhaustive()
+    }
+}
+
+impl JwtKeys {
+    pub fn new(keys: KeySet) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(keys)),
+        }
+    }
+
+    pub fn replace(&self, keys: KeySet) {
+        *self.inner.write().unwrap_or_else(|e| e.into_inner()) = keys;
+    }
+
+    /// 对外发布的公钥集合
+    pub fn jwks(&self) -> JwkSet {
+        self.inner
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .jwks
+            .clone()
+    }
+
+    /// 令牌携带的 kid 是否未知，用于判断是否需要重新加载密钥
+    pub fn has_unknown_kid(&self, token: &str) -> bool {
+        let Ok(Header { kid: Some(kid), .. }) = decode_header(token) else {
+            return false;
+        };
+        !self
+            .inner
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .verifying
+            .contains_key(&kid)
+    }
 }
 
-pub fn encode_token(user_id: Uuid, secret: &str, expires_in: i64) -> Result<String> {
+pub fn encode_token(user_id: Uuid, keys: &JwtKeys, expires_in: i64) -> Result<String> {
+    encode_token_with_purpose(user_id, None, keys, expires_in)
+}
+
+pub fn encode_token_with_purpose(
+    user_id: Uuid,
+    purpose: Option<TokenPurpose>,
+    keys: &JwtKeys,
+    expires_in: i64,
+) -> Result<String> {
+    encode_claims(&new_claims(user_id, purpose, None, expires_in), keys)
+}
+
+/// 签发由 `actor_id` 模拟 `user_id` 的令牌
+pub fn encode_impersonation_token(
+    user_id: Uuid,
+    actor_id: Uuid,
+    keys: &JwtKeys,
+    expires_in: i64,
+) -> Result<String> {
+    let act = Actor { sub: actor_id };
+    encode_claims(&new_claims(user_id, None, Some(act), expires_in), keys)
+}
+
+/// 签发两步验证挑战令牌，`challenge_id` 用于限制验证码的尝试次数
+pub fn encode_mfa_challenge_token(
+    user_id: Uuid,
+    challenge_id: Uuid,
+    keys: &JwtKeys,
+    expires_in: i64,
+) -> Result<String> {
+    let mut claims = new_claims(user_id, Some(TokenPurpose::MfaChallenge), None, expires_in);
+    claims.jti = Some(challenge_id);
+    encode_claims(&claims, keys)
+}
+
+fn new_claims(
+    user_id: Uuid,
+    purpose: Option<TokenPurpose>,
+    act: Option<Actor>,
+    expires_in: i64,
+) -> Claims {
     let now = Utc::now();
-    let exp = (now + Duration::seconds(expires_in)).timestamp() as usize;
-    let iat = now.timestamp() as usize;
 
-    let claims = Claims {
+    Claims {
         sub: user_id,
-        exp,
-        iat,
+        exp: (now + Duration::seconds(expires_in)).timestamp() as usize,
+        iat: now.timestamp() as usize,
+        purpose,
+        act,
+        jti: None,
+    }
+}
+
+fn encode_claims(claims: &Claims, keys: &JwtKeys) -> Result<String> {
+    let keys = keys.inner.read().unwrap_or_else(|e| e.into_inner());
+    let (header, key) = match (&keys.signing, &keys.secret) {
+        (Some(signing), _) => {
+            let mut header = Header::new(signing.algorithm);
+            header.kid = Some(signing.kid.clone());
+            (header, &signing.key)
+        }
+        (None, Some((secret, _))) => (Header::default(), secret),
+        (None, None) => return Err(anyhow!("未配置 JWT 签名密钥")),
+    };
+
+    encode(&header, claims, key).map_err(|e| anyhow!("JWT 编码失败: {}", e))
+}
+
+pub fn decode_token(token: &str, keys: &JwtKeys) -> Result<Claims> {
+    let header = decode_header(token).map_err(|e| anyhow!("JWT 解码失败: {}", e))?;
+    let keys = keys.inner.read().unwrap_or_else(|e| e.into_inner());
+
+    // 只接受与密钥匹配的算法，防止算法混淆
+    let (algorithm, key, issued_before) = match &header.kid {
+        Some(kid) => keys
+            .verifying
+            .get(kid)
+            .map(|(algorithm, key)| (*algorithm, key, None))
+            .ok_or_else(|| anyhow!("JWT 解码失败: 未知的 kid {}", kid))?,
+        None => keys
+            .secret
+            .as_ref()
+            .map(|(_, key)| (Algorithm::HS256, key, keys.secret_issued_before))
+            .ok_or_else(|| anyhow!("JWT 解码失败: 缺少 kid"))?,
     };
 
-    encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(secret.as_bytes()),
-    )
-    .map_err(|e| anyhow!("JWT 编码失败: {}", e))
-}
-
-pub fn decode_token(token: &str, secret: &str) -> Result<Claims> {
-    decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(secret.as_bytes()),
-        &Validation::default(),
-    )
-    .map(|data| data.claims)
-    .map_err(|e| anyhow!("JWT 解码失败: {}", e))
+    let claims = decode::<Claims>(token, key, &Validation::new(algorithm))
+        .map(|data| data.claims)
+        .map_err(|e| anyhow!("JWT 解码失败: {}", e))?;
+
+    // iat 精确到秒，与切换同一秒签发的令牌仍视为切换前签发
+    if issued_before.is_some_and(|before| claims.iat as i64 > before.timestamp()) {
+        return Err(anyhow!("JWT 解码失败: 共享密钥已停止签发令牌"));
+    }
+
+    Ok(claims)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secret_keys(issued_before: Option<DateTime<Utc>>) -> JwtKeys {
+        let keys = KeySet::new(None, Vec::new(), "test-secret").unwrap();
+        JwtKeys::new(match issued_before {
+            Some(before) => keys.restrict_secret(before),
+            None => keys,
+        })
+    }
+
+    #[test]
+    fn secret_tokens_are_accepted_without_restriction() {
+        let keys = secret_keys(None);
+        let token = encode_token(Uuid::nil(), &keys, 60).unwrap();
+        assert!(decode_token(&token, &keys).is_ok());
+    }
+
+    #[test]
+    fn restricted_secret_only_accepts_earlier_tokens() {
+        let token = encode_token(Uuid::nil(), &secret_keys(None), 60).unwrap();
+
+        let switched_later = secret_keys(Some(Utc::now() + Duration::seconds(10)));
+        assert!(decode_token(&token, &switched_later).is_ok());
+
+        let switched_earlier = secret_keys(Some(Utc::now() - Duration::seconds(10)));
+        assert!(decode_token(&token, &switched_earlier).is_err());
+    }
 }
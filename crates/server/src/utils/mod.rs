@@ -1,14 +1,66 @@
+pub mod api_key;
 pub mod jwt;
 
+use std::sync::OnceLock;
+
 use argon2::{
-    Argon2,
-    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString, rand_core::OsRng},
+    Algorithm, Argon2, Params, Version,
+    password_hash::{
+        PasswordHash, PasswordHasher, PasswordVerifier, SaltString,
+        rand_core::{OsRng, RngCore},
+    },
 };
 
+use crate::config::PasswordHashConfig;
+
+pub fn random_bytes(len: usize) -> Vec<u8> {
+    let mut buf = vec![0u8; len];
+    OsRng.fill_bytes(&mut buf);
+    buf
+}
+
+/// 常量时间比较，避免通过响应时间推测验证码
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// 生成指定字节数的随机值，以十六进制返回
+pub fn random_hex(bytes: usize) -> String {
+    hex::encode(random_bytes(bytes))
+}
+
+static PASSWORD_HASH_PARAMS: OnceLock<Params> = OnceLock::new();
+
+/// 设置新密码哈希使用的 Argon2 参数，应在启动时调用一次；未调用时使用默认参数
+pub fn init_password_hashing(config: &PasswordHashConfig) -> anyhow::Result<()> {
+    let params = Params::new(
+        config.memory_kib,
+        config.iterations,
+        config.parallelism,
+        None,
+    )
+    .map_err(|e| anyhow::anyhow!("Argon2 参数无效: {}", e))?;
+
+    PASSWORD_HASH_PARAMS
+        .set(params)
+        .map_err(|_| anyhow::anyhow!("密码哈希参数已初始化"))
+}
+
+fn argon2() -> Argon2<'static> {
+    let params = PASSWORD_HASH_PARAMS.get().cloned().unwrap_or_default();
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+}
+
+/// bcrypt 哈希（如从其他系统导入的账户）以 `$2a$`、`$2b$` 或 `$2y$` 开头
+fn is_bcrypt_hash(password_hash: &str) -> bool {
+    ["$2a$", "$2b$", "$2y$"]
+        .iter()
+        .any(|prefix| password_hash.starts_with(prefix))
+}
+
 pub fn hash_password(password: &str) -> anyhow::Result<String> {
     let salt = SaltString::generate(&mut OsRng);
-    let argon2 = Argon2::default();
-    let password_hash = argon2
+    let password_hash = argon2()
         .hash_password(password.as_bytes(), &salt)
         .map_err(|e| anyhow::anyhow!("密码哈希失败: {}", e))?
         .to_string();
@@ -16,9 +68,87 @@ pub fn hash_password(password: &str) -> anyhow::Result<String> {
 }
 
 pub fn verify_password(password: &str, password_hash: &str) -> anyhow::Result<bool> {
+    if is_bcrypt_hash(password_hash) {
+        return bcrypt::verify(password, password_hash)
+            .map_err(|e| anyhow::anyhow!("解析密码哈希失败: {}", e));
+    }
+
+    // 按哈希中记录的算法和参数验证，参数调整前生成的哈希仍然有效
     let parsed_hash =
         PasswordHash::new(password_hash).map_err(|e| anyhow::anyhow!("解析密码哈希失败: {}", e))?;
     Ok(Argon2::default()
         .verify_password(password.as_bytes(), &parsed_hash)
         .is_ok())
 }
+
+/// 哈希的算法或参数与当前配置不一致时需要重新哈希
+pub fn password_needs_rehash(password_hash: &str) -> bool {
+    if is_bcrypt_hash(password_hash) {
+        return true;
+    }
+
+    let Ok(parsed) = PasswordHash::new(password_hash) else {
+        return true;
+    };
+    let current = argon2();
+
+    parsed.algorithm != Algorithm::Argon2id.ident()
+        || parsed.version != Some(Version::V0x13.into())
+        || Params::try_from(&parsed).map_or(true, |params| {
+            params.m_cost() != current.params().m_cost()
+                || params.t_cost() != current.params().t_cost()
+                || params.p_cost() != current.params().p_cost()
+        })
+}
+
+/// 用于不存在的用户的占位哈希，使登录耗时与用户是否存在无关
+pub fn dummy_password_hash() -> &'static str {
+    static HASH: OnceLock<String> = OnceLock::new();
+    HASH.get_or_init(|| hash_password(&random_hex(16)).expect("生成占位密码哈希失败"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argon2_hash(algorithm: Algorithm, params: Params) -> String {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::new(algorithm, Version::V0x13, params)
+            .hash_password(b"secret", &salt)
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn current_params_do_not_need_rehash() {
+        let hash = hash_password("secret").unwrap();
+        assert!(verify_password("secret", &hash).unwrap());
+        assert!(!password_needs_rehash(&hash));
+    }
+
+    #[test]
+    fn bcrypt_hash_needs_rehash() {
+        let hash = bcrypt::hash("secret", 4).unwrap();
+        assert!(verify_password("secret", &hash).unwrap());
+        assert!(password_needs_rehash(&hash));
+    }
+
+    #[test]
+    fn old_argon2_params_need_rehash() {
+        let hash = argon2_hash(Algorithm::Argon2id, Params::new(4096, 1, 1, None).unwrap());
+        assert!(verify_password("secret", &hash).unwrap());
+        assert!(password_needs_rehash(&hash));
+    }
+
+    #[test]
+    fn other_argon2_variant_needs_rehash() {
+        let hash = argon2_hash(Algorithm::Argon2i, Params::default());
+        assert!(verify_password("secret", &hash).unwrap());
+        assert!(password_needs_rehash(&hash));
+    }
+
+    #[test]
+    fn unparsable_hash_needs_rehash() {
+        assert!(password_needs_rehash("not-a-hash"));
+    }
+}
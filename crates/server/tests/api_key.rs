@@ -0,0 +1,108 @@
+mod common;
+
+use axum_test::{TestResponse, TestServer};
+use serde_json::{Value, json};
+use server::config::Config;
+use sqlx::PgPool;
+
+async fn admin_token(server: &TestServer) -> String {
+    common::login(server, "admin", common::ADMIN_PASSWORD).await["token"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+async fn create_key(server: &TestServer, token: &str, body: Value) -> TestResponse {
+    server
+        .post("/api/keys")
+        .authorization_bearer(token)
+        .json(&body)
+        .await
+}
+
+async fn issue_key(server: &TestServer, body: Value) -> String {
+    let token = admin_token(server).await;
+    let response = create_key(server, &token, body).await;
+    response.assert_status(axum::http::StatusCode::CREATED);
+    response.json::<Value>()["key"].as_str().unwrap().to_string()
+}
+
+fn proxied_config() -> Config {
+    let mut config = common::config();
+    config.server.trust_proxy = true;
+    config
+}
+
+#[sqlx::test]
+async fn limits_key_to_its_scopes(pool: PgPool) {
+    let server = common::server(pool, common::config()).await;
+    let key = issue_key(&server, json!({ "name": "audit", "scopes": ["admin.audit"] })).await;
+
+    server
+        .get("/api/admin/audit-events")
+        .authorization_bearer(&key)
+        .await
+        .assert_status_ok();
+
+    // 用户本身拥有该权限，但不在 key 的 scopes 之内
+    let response = server
+        .get("/api/admin/settings")
+        .authorization_bearer(&key)
+        .await;
+    response.assert_status_forbidden();
+    assert_eq!(response.json::<Value>()["code"], "permission_denied");
+}
+
+#[sqlx::test]
+async fn rejects_requests_outside_allowed_ips(pool: PgPool) {
+    let server = common::server(pool, proxied_config()).await;
+    let key = issue_key(
+        &server,
+        json!({
+            "name": "internal",
+            "scopes": ["admin.audit"],
+            "allowed_ips": ["10.0.0.0/8", "192.0.2.7"],
+        }),
+    )
+    .await;
+
+    for ip in ["10.1.2.3", "192.0.2.7"] {
+        server
+            .get("/api/admin/audit-events")
+            .authorization_bearer(&key)
+            .add_header("X-Forwarded-For", ip)
+            .await
+            .assert_status_ok();
+    }
+
+    let response = server
+        .get("/api/admin/audit-events")
+        .authorization_bearer(&key)
+        .add_header("X-Forwarded-For", "192.0.2.8")
+        .await;
+    response.assert_status_forbidden();
+    assert_eq!(response.json::<Value>()["code"], "ip_not_allowed");
+}
+
+#[sqlx::test]
+async fn rejects_malformed_allowed_ips(pool: PgPool) {
+    let server = common::server(pool, common::config()).await;
+    let token = admin_token(&server).await;
+
+    for entry in ["10.0.0.0/33", "localhost", ""] {
+        let response = create_key(
+            &server,
+            &token,
+            json!({ "name": "bad", "scopes": ["admin.audit"], "allowed_ips": [entry] }),
+        )
+        .await;
+        common::assert_validation_error(&response, "allowed_ips");
+    }
+
+    let keys: Value = server
+        .get("/api/keys")
+        .authorization_bearer(&token)
+        .await
+        .json();
+    assert_eq!(keys.as_array().unwrap().len(), 0);
+}
@@ -0,0 +1,185 @@
+//! 集成测试共用的应用构建工具
+//!
+//! 测试使用 `#[sqlx::test]`，需要通过 `DATABASE_URL` 指定可创建数据库的 PostgreSQL 连接
+
+#![allow(dead_code)]
+
+use std::{
+    collections::HashSet,
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use axum_test::{TestResponse, TestServer};
+use chrono::Utc;
+use serde_json::{Value, json};
+use server::{
+    config::{
+        BootstrapConfig, Config, DatabaseConfig, JwtAlgorithm, JwtConfig, MailConfig,
+        MailTransport, PasswordHashConfig, ServerConfig, SmtpTls, WebauthnConfig,
+    },
+    database,
+    models::User,
+    routes,
+    services::{GroupService, UserService},
+    utils::hash_password,
+};
+use sqlx::PgPool;
+
+pub const ADMIN_PASSWORD: &str = "Integration-Test-1";
+
+/// 不读取环境变量的最小配置
+pub fn config() -> Config {
+    Config {
+        server: ServerConfig {
+            port: 0,
+            host: "127.0.0.1".to_string(),
+            trust_proxy: false,
+            trusted_proxies: Vec::new(),
+            public_url: "http://localhost:3000".to_string(),
+        },
+        database: DatabaseConfig {
+            url: String::new(),
+            max_connections: 1,
+        },
+        jwt: JwtConfig {
+            algorithm: JwtAlgorithm::Hs256,
+            secret: "integration-test-secret".to_string(),
+            expires_in: 3600,
+        },
+        password_hash: PasswordHashConfig {
+            memory_kib: argon2::Params::DEFAULT_M_COST,
+            iterations: argon2::Params::DEFAULT_T_COST,
+            parallelism: argon2::Params::DEFAULT_P_COST,
+        },
+        oidc: Vec::new(),
+        ldap: Vec::new(),
+        webauthn: WebauthnConfig {
+            rp_id: "localhost".to_string(),
+            rp_origin: "http://localhost:3000".to_string(),
+            rp_name: "RikkaHub".to_string(),
+        },
+        bootstrap: BootstrapConfig {
+            admin_password: Some(ADMIN_PASSWORD.to_string()),
+        },
+        mail: MailConfig {
+            transport: MailTransport::Log,
+            from: "RikkaHub <noreply@localhost>".to_string(),
+            smtp_host: "localhost".to_string(),
+            smtp_port: None,
+            smtp_username: None,
+            smtp_password: None,
+            smtp_tls: SmtpTls::StartTls,
+            file_dir: String::new(),
+        },
+    }
+}
+
+/// 初始化种子数据并构建应用
+pub async fn server(pool: PgPool, config: Config) -> TestServer {
+    database::seed(&pool, &config.bootstrap)
+        .await
+        .expect("初始化种子数据失败");
+    let router = routes::create_router(pool, config)
+        .await
+        .expect("构建路由失败");
+    TestServer::new(router).expect("启动测试服务失败")
+}
+
+/// 用密码登录并返回响应
+pub async fn login(server: &TestServer, username: &str, password: &str) -> Value {
+    server
+        .post("/auth/login")
+        .json(&json!({ "username": username, "password": password }))
+        .await
+        .json()
+}
+
+/// 断言响应为指定字段的校验错误
+pub fn assert_validation_error(response: &TestResponse, field: &str) {
+    response.assert_status_bad_request();
+    let body: Value = response.json();
+    assert_eq!(body["code"], "validation_failed");
+    assert_eq!(body["details"][0]["field"], field);
+}
+
+/// 创建带密码的普通用户，`email` 视为已验证
+pub async fn create_user(
+    pool: &PgPool,
+    username: &str,
+    password: &str,
+    email: Option<&str>,
+) -> User {
+    let mut user = User::new(username.to_string(), Some(hash_password(password).unwrap()));
+    user.email = email.map(str::to_string);
+    user.email_verified_at = email.map(|_| Utc::now());
+    let user = UserService::create(pool, &user).await.unwrap();
+    GroupService::add_user_to_default_groups(pool, user.id)
+        .await
+        .unwrap();
+    user
+}
+
+/// 使用文件发送方式收取邮件，从正文中读取链接里的令牌
+pub struct Mailbox {
+    dir: PathBuf,
+    seen: Mutex<HashSet<PathBuf>>,
+}
+
+impl Mailbox {
+    pub fn new() -> Self {
+        Self {
+            dir: std::env::temp_dir().join(format!("rikkahub-mail-{}", uuid::Uuid::new_v4())),
+            seen: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub fn configure(&self, config: &mut Config) {
+        config.mail.transport = MailTransport::File;
+        config.mail.file_dir = self.dir.to_string_lossy().into_owned();
+    }
+
+    fn unseen(&self) -> Vec<PathBuf> {
+        let seen = self.seen.lock().unwrap();
+        std::fs::read_dir(&self.dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok().map(|e| e.path()))
+                    .filter(|path| !seen.contains(path))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// 等待下一封邮件（邮件在后台发送），返回其中 `token=` 之后的令牌
+    pub async fn next_token(&self) -> String {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            if let Some(path) = self.unseen().into_iter().next() {
+                let content = std::fs::read_to_string(&path).unwrap();
+                // 文件可能尚未写完
+                if let Some((_, rest)) = content.split_once("token=")
+                    && content.ends_with('\n')
+                {
+                    self.seen.lock().unwrap().insert(path);
+                    return rest.chars().take_while(char::is_ascii_hexdigit).collect();
+                }
+            }
+            assert!(Instant::now() < deadline, "等待邮件超时");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+
+    /// 留出后台发送的时间后确认没有新邮件
+    pub async fn assert_empty(&self) {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(self.unseen().is_empty(), "收到了不应发送的邮件");
+    }
+}
+
+impl Drop for Mailbox {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
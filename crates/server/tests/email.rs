@@ -0,0 +1,125 @@
+mod common;
+
+use axum_test::{TestResponse, TestServer};
+use serde_json::{Value, json};
+use sqlx::PgPool;
+
+const PASSWORD: &str = "Bob-Passw0rd";
+
+async fn setup(pool: &PgPool) -> (TestServer, common::Mailbox, String) {
+    let mailbox = common::Mailbox::new();
+    let mut config = common::config();
+    mailbox.configure(&mut config);
+    let server = common::server(pool.clone(), config).await;
+    common::create_user(pool, "bob", PASSWORD, None).await;
+    let token = common::login(&server, "bob", PASSWORD).await["token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    (server, mailbox, token)
+}
+
+async fn me(server: &TestServer, token: &str) -> Value {
+    server
+        .get("/auth/me")
+        .authorization_bearer(token)
+        .await
+        .json()
+}
+
+async fn confirm(server: &TestServer, token: &str) -> TestResponse {
+    server
+        .post("/auth/email/confirm")
+        .json(&json!({ "token": token }))
+        .await
+}
+
+async fn resend(server: &TestServer, token: &str) -> TestResponse {
+    server
+        .post("/auth/email/verification")
+        .authorization_bearer(token)
+        .await
+}
+
+#[sqlx::test]
+async fn confirms_changed_email_once(pool: PgPool) {
+    let (server, mailbox, token) = setup(&pool).await;
+
+    server
+        .put("/auth/email")
+        .authorization_bearer(&token)
+        .json(&json!({ "email": "bob@example.com" }))
+        .await
+        .assert_status(axum::http::StatusCode::ACCEPTED);
+    // 确认前不替换当前邮箱
+    assert!(me(&server, &token).await["email"].is_null());
+
+    let verification = mailbox.next_token().await;
+    confirm(&server, &verification)
+        .await
+        .assert_status(axum::http::StatusCode::NO_CONTENT);
+
+    let user = me(&server, &token).await;
+    assert_eq!(user["email"], "bob@example.com");
+    assert!(user["email_verified_at"].is_string());
+
+    common::assert_validation_error(&confirm(&server, &verification).await, "token");
+}
+
+#[sqlx::test]
+async fn resend_replaces_previous_token(pool: PgPool) {
+    let (server, mailbox, token) = setup(&pool).await;
+    sqlx::query("UPDATE users SET email = 'bob@example.com' WHERE username = 'bob'")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    resend(&server, &token)
+        .await
+        .assert_status(axum::http::StatusCode::ACCEPTED);
+    let first = mailbox.next_token().await;
+
+    // 发送间隔内不再发送
+    let response = resend(&server, &token).await;
+    response.assert_status(axum::http::StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.json::<Value>()["code"], "rate_limited");
+    mailbox.assert_empty().await;
+
+    sqlx::query("UPDATE email_verification_tokens SET created_at = NOW() - INTERVAL '2 minutes'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    resend(&server, &token)
+        .await
+        .assert_status(axum::http::StatusCode::ACCEPTED);
+    let second = mailbox.next_token().await;
+
+    common::assert_validation_error(&confirm(&server, &first).await, "token");
+    confirm(&server, &second)
+        .await
+        .assert_status(axum::http::StatusCode::NO_CONTENT);
+
+    let response = resend(&server, &token).await;
+    response.assert_status_bad_request();
+    assert_eq!(response.json::<Value>()["code"], "bad_request");
+}
+
+#[sqlx::test]
+async fn rejects_email_confirmed_by_another_user(pool: PgPool) {
+    let (server, mailbox, token) = setup(&pool).await;
+
+    server
+        .put("/auth/email")
+        .authorization_bearer(&token)
+        .json(&json!({ "email": "shared@example.com" }))
+        .await
+        .assert_status(axum::http::StatusCode::ACCEPTED);
+    let verification = mailbox.next_token().await;
+
+    // 发送验证邮件后该地址被其他用户确认
+    common::create_user(&pool, "carol", PASSWORD, Some("shared@example.com")).await;
+
+    let response = confirm(&server, &verification).await;
+    response.assert_status(axum::http::StatusCode::CONFLICT);
+    assert!(me(&server, &token).await["email"].is_null());
+}
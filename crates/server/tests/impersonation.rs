@@ -0,0 +1,155 @@
+mod common;
+
+use std::time::Duration;
+
+use axum_test::TestServer;
+use serde_json::{Value, json};
+use server::{models::User, services::GroupService};
+use sqlx::PgPool;
+
+const PASSWORD: &str = "Staff-Passw0rd";
+
+struct Setup {
+    server: TestServer,
+    operator: User,
+    operator_token: String,
+    target: User,
+}
+
+/// 创建 admin 组中的操作员和一个普通用户
+async fn setup(pool: &PgPool) -> Setup {
+    let server = common::server(pool.clone(), common::config()).await;
+    let operator = common::create_user(pool, "olivia", PASSWORD, None).await;
+    let admin = GroupService::find_by_name(pool, "admin")
+        .await
+        .unwrap()
+        .unwrap();
+    GroupService::add_user(pool, operator.id, admin.id)
+        .await
+        .unwrap();
+    let target = common::create_user(pool, "bob", PASSWORD, None).await;
+
+    let operator_token = common::login(&server, "olivia", PASSWORD).await["token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    Setup {
+        server,
+        operator,
+        operator_token,
+        target,
+    }
+}
+
+async fn impersonate(setup: &Setup) -> String {
+    let response = setup
+        .server
+        .post(&format!("/api/admin/users/{}/impersonate", setup.target.id))
+        .authorization_bearer(&setup.operator_token)
+        .await;
+    response.assert_status_ok();
+    response.json::<Value>()["token"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+#[sqlx::test]
+async fn acts_as_user_and_records_audit_event(pool: PgPool) {
+    let setup = setup(&pool).await;
+    let token = impersonate(&setup).await;
+
+    let me: Value = setup
+        .server
+        .get("/auth/me")
+        .authorization_bearer(&token)
+        .await
+        .json();
+    assert_eq!(me["id"], setup.target.id.to_string());
+    assert_eq!(me["act"]["id"], setup.operator.id.to_string());
+    assert_eq!(me["act"]["username"], "olivia");
+
+    // 模拟登录时不能执行敏感操作
+    setup
+        .server
+        .put("/auth/password")
+        .authorization_bearer(&token)
+        .json(&json!({ "current_password": PASSWORD, "new_password": "Other-Passw0rd" }))
+        .await
+        .assert_status_forbidden();
+
+    let (actor_id, target_id): (uuid::Uuid, String) = sqlx::query_as(
+        "SELECT actor_id, target_id FROM audit_events WHERE action = 'auth.impersonate'",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(actor_id, setup.operator.id);
+    assert_eq!(target_id, setup.target.id.to_string());
+}
+
+#[sqlx::test]
+async fn requires_impersonate_permission(pool: PgPool) {
+    let setup = setup(&pool).await;
+    let token = common::login(&setup.server, "bob", PASSWORD).await["token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    setup
+        .server
+        .post(&format!(
+            "/api/admin/users/{}/impersonate",
+            setup.operator.id
+        ))
+        .authorization_bearer(&token)
+        .await
+        .assert_status_forbidden();
+}
+
+#[sqlx::test]
+async fn revokes_token_when_operator_loses_permission(pool: PgPool) {
+    let setup = setup(&pool).await;
+    let token = impersonate(&setup).await;
+
+    let admin = GroupService::find_by_name(&pool, "admin")
+        .await
+        .unwrap()
+        .unwrap();
+    GroupService::remove_user(&pool, setup.operator.id, admin.id)
+        .await
+        .unwrap();
+
+    let response = setup
+        .server
+        .get("/auth/me")
+        .authorization_bearer(&token)
+        .await;
+    response.assert_status_unauthorized();
+    assert_eq!(response.json::<Value>()["code"], "invalid_token");
+}
+
+#[sqlx::test]
+async fn revokes_token_when_operator_changes_password(pool: PgPool) {
+    let setup = setup(&pool).await;
+    let token = impersonate(&setup).await;
+    // 令牌的签发时间精确到秒
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+
+    setup
+        .server
+        .put("/auth/password")
+        .authorization_bearer(&setup.operator_token)
+        .json(&json!({ "current_password": PASSWORD, "new_password": "Other-Passw0rd" }))
+        .await
+        .assert_status_ok();
+
+    let response = setup
+        .server
+        .get("/auth/me")
+        .authorization_bearer(&token)
+        .await;
+    response.assert_status_unauthorized();
+    assert_eq!(response.json::<Value>()["code"], "session_revoked");
+}
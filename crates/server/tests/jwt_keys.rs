@@ -0,0 +1,101 @@
+mod common;
+
+use axum_test::TestServer;
+use jsonwebtoken::{DecodingKey, Validation, jwk::JwkSet};
+use serde_json::Value;
+use server::config::{Config, JwtAlgorithm};
+use sqlx::PgPool;
+
+fn eddsa_config() -> Config {
+    let mut config = common::config();
+    config.jwt.algorithm = JwtAlgorithm::EdDsa;
+    config
+}
+
+async fn admin_token(server: &TestServer) -> String {
+    common::login(server, "admin", common::ADMIN_PASSWORD).await["token"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+fn kid(token: &str) -> String {
+    jsonwebtoken::decode_header(token).unwrap().kid.unwrap()
+}
+
+async fn jwks(server: &TestServer) -> JwkSet {
+    server.get("/.well-known/jwks.json").await.json()
+}
+
+async fn me(server: &TestServer, token: &str) -> axum_test::TestResponse {
+    server.get("/auth/me").authorization_bearer(token).await
+}
+
+#[sqlx::test]
+async fn rotation_keeps_previous_key_for_verification(pool: PgPool) {
+    let server = common::server(pool, eddsa_config()).await;
+    let old_token = admin_token(&server).await;
+    let old_kid = kid(&old_token);
+    assert_eq!(jwks(&server).await.keys.len(), 1);
+
+    let response = server
+        .post("/api/admin/jwt-keys/rotate")
+        .authorization_bearer(&old_token)
+        .await;
+    response.assert_status(axum::http::StatusCode::CREATED);
+    let new_kid = response.json::<Value>()["kid"].as_str().unwrap().to_string();
+    assert_ne!(new_kid, old_kid);
+
+    // 轮换前签发的令牌仍然有效，新令牌使用新密钥
+    me(&server, &old_token).await.assert_status_ok();
+    let new_token = admin_token(&server).await;
+    assert_eq!(kid(&new_token), new_kid);
+
+    // 其他服务只凭 JWKS 即可验证令牌
+    let jwks = jwks(&server).await;
+    assert_eq!(jwks.keys.len(), 2);
+    for token in [&old_token, &new_token] {
+        let jwk = jwks.find(&kid(token)).unwrap();
+        let claims = jsonwebtoken::decode::<Value>(
+            token,
+            &DecodingKey::from_jwk(jwk).unwrap(),
+            &Validation::new(jsonwebtoken::Algorithm::EdDSA),
+        )
+        .unwrap()
+        .claims;
+        assert!(claims["sub"].is_string());
+    }
+}
+
+#[sqlx::test]
+async fn rejects_rotation_with_shared_secret(pool: PgPool) {
+    let server = common::server(pool, common::config()).await;
+    let token = admin_token(&server).await;
+
+    let response = server
+        .post("/api/admin/jwt-keys/rotate")
+        .authorization_bearer(&token)
+        .await;
+    response.assert_status_bad_request();
+    assert!(jwks(&server).await.keys.is_empty());
+}
+
+#[sqlx::test]
+async fn accepts_secret_signed_tokens_only_until_they_expire(pool: PgPool) {
+    let hs256 = common::server(pool.clone(), common::config()).await;
+    let legacy_token = admin_token(&hs256).await;
+
+    // 切换到非对称签名后，切换前签发的令牌在有效期内仍可使用
+    let server = common::server(pool.clone(), eddsa_config()).await;
+    me(&server, &legacy_token).await.assert_status_ok();
+
+    sqlx::query("UPDATE jwt_signing_keys SET created_at = created_at - $1 * INTERVAL '1 second'")
+        .bind(common::config().jwt.expires_in + 1)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let server = common::server(pool, eddsa_config()).await;
+    let response = me(&server, &legacy_token).await;
+    response.assert_status_unauthorized();
+    assert_eq!(response.json::<Value>()["code"], "invalid_token");
+}
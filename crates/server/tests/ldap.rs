@@ -0,0 +1,401 @@
+mod common;
+
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use axum_test::{TestResponse, TestServer};
+use chrono::Utc;
+use serde_json::{Value, json};
+use server::{config::LdapDirectoryConfig, models::User, services::UserService};
+use sqlx::PgPool;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+const ALICE_DN: &str = "uid=alice,ou=people,dc=example,dc=com";
+const BOB_DN: &str = "uid=bob,ou=people,dc=example,dc=com";
+const ADMINS_DN: &str = "CN=Admins,ou=groups,dc=example,dc=com";
+const STAFF_DN: &str = "cn=staff,ou=groups,dc=example,dc=com";
+
+/// LDAP resultCode
+const RC_SUCCESS: u8 = 0;
+const RC_INVALID_CREDENTIALS: u8 = 49;
+
+/// LDAP 协议操作的 BER 标签
+const TAG_BIND_REQUEST: u8 = 0x60;
+const TAG_BIND_RESPONSE: u8 = 0x61;
+const TAG_UNBIND_REQUEST: u8 = 0x42;
+const TAG_SEARCH_REQUEST: u8 = 0x63;
+const TAG_SEARCH_ENTRY: u8 = 0x64;
+const TAG_SEARCH_DONE: u8 = 0x65;
+
+struct Entry {
+    password: &'static str,
+    attributes: Vec<(&'static str, Vec<&'static str>)>,
+}
+
+/// 进程内的 LDAP 目录，只实现简单绑定和对已绑定用户自身条目的查询
+#[derive(Clone)]
+struct Directory {
+    url: String,
+    entries: Arc<Mutex<HashMap<&'static str, Entry>>>,
+    binds: Arc<AtomicUsize>,
+}
+
+impl Directory {
+    async fn start() -> Self {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let entries = HashMap::from([
+            (
+                ALICE_DN,
+                Entry {
+                    password: "alice-secret",
+                    attributes: vec![
+                        ("uid", vec!["alice"]),
+                        ("mail", vec!["alice@corp.example.com"]),
+                        ("displayName", vec!["Alice Liddell"]),
+                        ("memberOf", vec![ADMINS_DN, STAFF_DN]),
+                    ],
+                },
+            ),
+            (
+                BOB_DN,
+                Entry {
+                    password: "bob-secret",
+                    attributes: vec![
+                        ("uid", vec!["bob"]),
+                        ("mail", vec!["bob@corp.example.com"]),
+                        ("memberOf", vec![STAFF_DN]),
+                    ],
+                },
+            ),
+        ]);
+
+        let directory = Self {
+            url: format!("ldap://{}", listener.local_addr().unwrap()),
+            entries: Arc::new(Mutex::new(entries)),
+            binds: Arc::new(AtomicUsize::new(0)),
+        };
+
+        let server = directory.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(server.clone().serve(stream));
+            }
+        });
+
+        directory
+    }
+
+    fn config(&self, domains: &[&str]) -> LdapDirectoryConfig {
+        LdapDirectoryConfig {
+            name: "corp".to_string(),
+            url: self.url.clone(),
+            starttls: false,
+            domains: domains.iter().map(|d| d.to_string()).collect(),
+            bind_dn: "uid={username},ou=people,dc=example,dc=com".to_string(),
+            base_dn: "ou=people,dc=example,dc=com".to_string(),
+            user_filter: "(uid={username})".to_string(),
+            username_attribute: "uid".to_string(),
+            email_attribute: "mail".to_string(),
+            trust_email: true,
+            name_attribute: "displayName".to_string(),
+            group_attribute: "memberOf".to_string(),
+            group_mapping: HashMap::from([(ADMINS_DN.to_lowercase(), "admin".to_string())]),
+        }
+    }
+
+    fn set_groups(&self, dn: &str, groups: Vec<&'static str>) {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.get_mut(dn).unwrap();
+        for (name, values) in &mut entry.attributes {
+            if *name == "memberOf" {
+                *values = groups.clone();
+            }
+        }
+    }
+
+    async fn serve(self, mut stream: tokio::net::TcpStream) {
+        let mut buf = Vec::new();
+        let mut bound: Option<String> = None;
+
+        loop {
+            while let Some((_, message, consumed)) = read_tlv(&buf) {
+                let (_, message_id, rest) = read_tlv(message).unwrap();
+                let message_id = message_id.to_vec();
+                let (tag, op, _) = read_tlv(&message[rest..]).unwrap();
+                let op = op.to_vec();
+                buf.drain(..consumed);
+
+                let reply = match tag {
+                    TAG_BIND_REQUEST => {
+                        let (dn, password) = bind_credentials(&op);
+                        bound = self.bind(&dn, &password);
+                        let rc = match bound {
+                            Some(_) => RC_SUCCESS,
+                            None => RC_INVALID_CREDENTIALS,
+                        };
+                        message_reply(&message_id, result(TAG_BIND_RESPONSE, rc))
+                    }
+                    TAG_SEARCH_REQUEST => {
+                        let mut reply = bound
+                            .as_deref()
+                            .and_then(|dn| self.search_entry(dn, &op))
+                            .map(|entry| message_reply(&message_id, entry))
+                            .unwrap_or_default();
+                        reply.extend(message_reply(
+                            &message_id,
+                            result(TAG_SEARCH_DONE, RC_SUCCESS),
+                        ));
+                        reply
+                    }
+                    TAG_UNBIND_REQUEST => return,
+                    _ => panic!("未实现的 LDAP 操作: {:#x}", tag),
+                };
+                if stream.write_all(&reply).await.is_err() {
+                    return;
+                }
+            }
+
+            let mut chunk = [0u8; 4096];
+            match stream.read(&mut chunk).await {
+                Ok(0) | Err(_) => return,
+                Ok(n) => buf.extend_from_slice(&chunk[..n]),
+            }
+        }
+    }
+
+    fn bind(&self, dn: &str, password: &str) -> Option<String> {
+        self.binds.fetch_add(1, Ordering::SeqCst);
+        let dn = dn.to_lowercase();
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(dn.as_str())
+            .filter(|entry| entry.password == password)
+            .map(|_| dn)
+    }
+
+    /// 过滤器中包含已绑定用户的 uid 时返回其条目
+    fn search_entry(&self, dn: &str, op: &[u8]) -> Option<Vec<u8>> {
+        let entries = self.entries.lock().unwrap();
+        let entry = &entries[dn];
+        let (_, uid) = entry.attributes.iter().find(|(name, _)| *name == "uid")?;
+        op.windows(uid[0].len())
+            .any(|window| window == uid[0].as_bytes())
+            .then_some(())?;
+
+        let attributes: Vec<u8> = entry
+            .attributes
+            .iter()
+            .flat_map(|(name, values)| {
+                let values: Vec<u8> = values.iter().flat_map(|v| octet(v)).collect();
+                tlv(0x30, [octet(name), tlv(0x31, values)].concat())
+            })
+            .collect();
+        // 目录返回的 DN 大小写与绑定时不同
+        let object_name = dn.replace("uid=", "UID=");
+        Some(tlv(
+            TAG_SEARCH_ENTRY,
+            [octet(&object_name), tlv(0x30, attributes)].concat(),
+        ))
+    }
+}
+
+/// 解析一个完整的 BER 元素，返回标签、内容和占用的字节数
+fn read_tlv(buf: &[u8]) -> Option<(u8, &[u8], usize)> {
+    let tag = *buf.first()?;
+    let first = *buf.get(1)? as usize;
+    let (len, header) = if first & 0x80 == 0 {
+        (first, 2)
+    } else {
+        let n = first & 0x7f;
+        let bytes = buf.get(2..2 + n)?;
+        (
+            bytes.iter().fold(0usize, |acc, b| acc << 8 | *b as usize),
+            2 + n,
+        )
+    };
+    let body = buf.get(header..header + len)?;
+    Some((tag, body, header + len))
+}
+
+fn tlv(tag: u8, body: Vec<u8>) -> Vec<u8> {
+    let len = body.len();
+    let mut out = vec![tag];
+    match len {
+        0..=0x7f => out.push(len as u8),
+        0x80..=0xff => out.extend([0x81, len as u8]),
+        _ => {
+            out.push(0x82);
+            out.extend((len as u16).to_be_bytes());
+        }
+    }
+    out.extend(body);
+    out
+}
+
+fn octet(value: &str) -> Vec<u8> {
+    tlv(0x04, value.as_bytes().to_vec())
+}
+
+fn result(tag: u8, rc: u8) -> Vec<u8> {
+    tlv(tag, [tlv(0x0a, vec![rc]), octet(""), octet("")].concat())
+}
+
+fn message_reply(message_id: &[u8], op: Vec<u8>) -> Vec<u8> {
+    tlv(0x30, [tlv(0x02, message_id.to_vec()), op].concat())
+}
+
+/// BindRequest: version, name, simple 认证的密码
+fn bind_credentials(op: &[u8]) -> (String, String) {
+    let (_, _, offset) = read_tlv(op).unwrap();
+    let (_, dn, len) = read_tlv(&op[offset..]).unwrap();
+    let (_, password, _) = read_tlv(&op[offset + len..]).unwrap();
+    (
+        String::from_utf8(dn.to_vec()).unwrap(),
+        String::from_utf8(password.to_vec()).unwrap(),
+    )
+}
+
+async fn setup(pool: PgPool, domains: &[&str]) -> (TestServer, Directory) {
+    let directory = Directory::start().await;
+    let mut config = common::config();
+    config.ldap = vec![directory.config(domains)];
+    (common::server(pool, config).await, directory)
+}
+
+async fn sign_in(server: &TestServer, username: &str, password: &str) -> TestResponse {
+    server
+        .post("/auth/login")
+        .json(&json!({ "username": username, "password": password }))
+        .await
+}
+
+async fn group_names(pool: &PgPool, user_id: &str) -> Vec<String> {
+    sqlx::query_scalar(
+        r#"
+        SELECT g.name FROM groups g
+        JOIN user_groups ug ON ug.group_id = g.id
+        WHERE ug.user_id = $1::uuid
+        ORDER BY g.name
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .unwrap()
+}
+
+async fn user_count(pool: &PgPool) -> i64 {
+    sqlx::query_scalar("SELECT COUNT(*) FROM users")
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+#[sqlx::test]
+async fn provisions_user_and_syncs_groups(pool: PgPool) {
+    let (server, directory) = setup(pool.clone(), &["corp.example.com"]).await;
+
+    let response = sign_in(&server, "alice@corp.example.com", "alice-secret").await;
+    response.assert_status_ok();
+
+    let body: Value = response.json();
+    assert!(body["token"].is_string());
+    assert_eq!(body["user"]["username"], "alice");
+    assert_eq!(body["user"]["email"], "alice@corp.example.com");
+
+    let user_id = body["user"]["id"].as_str().unwrap();
+    assert_eq!(group_names(&pool, user_id).await, ["admin", "users"]);
+
+    let subject: String = sqlx::query_scalar(
+        "SELECT subject FROM user_identities WHERE provider = 'ldap:corp' AND user_id = $1::uuid",
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(subject, ALICE_DN);
+
+    // 再次登录使用同一账户，目录中移除的用户组同步移除
+    directory.set_groups(ALICE_DN, vec![STAFF_DN]);
+    let again = sign_in(&server, "alice@corp.example.com", "alice-secret").await;
+    again.assert_status_ok();
+    assert_eq!(again.json::<Value>()["user"]["id"], user_id);
+    assert_eq!(group_names(&pool, user_id).await, ["users"]);
+}
+
+#[sqlx::test]
+async fn does_not_link_untrusted_email(pool: PgPool) {
+    let directory = Directory::start().await;
+    let mut config = common::config();
+    config.ldap = vec![LdapDirectoryConfig {
+        trust_email: false,
+        ..directory.config(&["corp.example.com"])
+    }];
+    let server = common::server(pool.clone(), config).await;
+
+    let mut local = User::new("alice-local".to_string(), None);
+    local.email = Some("alice@corp.example.com".to_string());
+    local.email_verified_at = Some(Utc::now());
+    let local = UserService::create(&pool, &local).await.unwrap();
+
+    let response = sign_in(&server, "alice@corp.example.com", "alice-secret").await;
+    response.assert_status_ok();
+    let body: Value = response.json();
+    assert_ne!(body["user"]["id"], local.id.to_string());
+    assert_eq!(body["user"]["username"], "alice");
+    assert!(body["user"]["email"].is_null());
+}
+
+#[sqlx::test]
+async fn rejects_wrong_password(pool: PgPool) {
+    let (server, directory) = setup(pool.clone(), &["corp.example.com"]).await;
+    let before = user_count(&pool).await;
+
+    let response = sign_in(&server, "alice@corp.example.com", "wrong-password").await;
+    response.assert_status_unauthorized();
+    assert_eq!(response.json::<Value>()["code"], "invalid_credentials");
+
+    // 空密码不会发起匿名绑定
+    let response = sign_in(&server, "alice@corp.example.com", "").await;
+    response.assert_status_unauthorized();
+
+    assert_eq!(directory.binds.load(Ordering::SeqCst), 1);
+    assert_eq!(user_count(&pool).await, before);
+}
+
+#[sqlx::test]
+async fn falls_back_to_directory_without_local_password(pool: PgPool) {
+    let (server, directory) = setup(pool.clone(), &[]).await;
+
+    // 本地密码优先，不访问目录
+    let admin = common::login(&server, "admin", common::ADMIN_PASSWORD).await;
+    assert!(admin["token"].is_string());
+    assert_eq!(directory.binds.load(Ordering::SeqCst), 0);
+
+    let response = sign_in(&server, "bob", "bob-secret").await;
+    response.assert_status_ok();
+    assert_eq!(response.json::<Value>()["user"]["username"], "bob");
+    assert_eq!(directory.binds.load(Ordering::SeqCst), 1);
+}
+
+#[sqlx::test]
+async fn reports_unreachable_directory(pool: PgPool) {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("ldap://{}", listener.local_addr().unwrap());
+    drop(listener);
+
+    let mut directory = Directory::start().await.config(&["corp.example.com"]);
+    directory.url = url;
+    let mut config = common::config();
+    config.ldap = vec![directory];
+    let server = common::server(pool, config).await;
+
+    let response = sign_in(&server, "alice@corp.example.com", "alice-secret").await;
+    response.assert_status(axum::http::StatusCode::BAD_GATEWAY);
+    assert_eq!(response.json::<Value>()["code"], "upstream_error");
+}
@@ -0,0 +1,17 @@
+mod common;
+
+use serde_json::{Value, json};
+use sqlx::PgPool;
+
+#[sqlx::test]
+async fn rejects_overlong_username_as_invalid_credentials(pool: PgPool) {
+    let server = common::server(pool, common::config()).await;
+
+    // 超过用户名和限制表字段长度的登录名仍按普通的凭据错误处理
+    let response = server
+        .post("/auth/login")
+        .json(&json!({ "username": "a".repeat(1000), "password": "whatever" }))
+        .await;
+    response.assert_status_unauthorized();
+    assert_eq!(response.json::<Value>()["code"], "invalid_credentials");
+}
@@ -0,0 +1,314 @@
+mod common;
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use aws_lc_rs::{
+    rand::SystemRandom,
+    signature::{Ed25519KeyPair, KeyPair},
+};
+use axum::{Json, Router, extract::State, routing::get, routing::post};
+use axum_test::{TestResponse, TestServer};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::Utc;
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use serde_json::{Value, json};
+use server::{
+    config::{OidcProviderConfig, OidcProviderKind},
+    models::User,
+    services::UserService,
+};
+use sqlx::PgPool;
+use url::Url;
+
+const CLIENT_ID: &str = "rikkahub";
+const KID: &str = "stub-key";
+
+/// 进程内的 OIDC 提供方，签发的 id_token 内容由测试指定
+#[derive(Clone)]
+struct Issuer {
+    url: String,
+    key: Arc<Vec<u8>>,
+    public_key: Arc<Vec<u8>>,
+    claims: Arc<Mutex<Value>>,
+}
+
+impl Issuer {
+    async fn start() -> Self {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+
+        let issuer = Self {
+            url: format!("http://{}", listener.local_addr().unwrap()),
+            key: Arc::new(pkcs8.as_ref().to_vec()),
+            public_key: Arc::new(pair.public_key().as_ref().to_vec()),
+            claims: Arc::new(Mutex::new(json!({}))),
+        };
+
+        let app = Router::new()
+            .route("/.well-known/openid-configuration", get(discovery))
+            .route("/jwks", get(jwks))
+            .route("/token", post(token))
+            .with_state(issuer.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        issuer
+    }
+
+    fn provider(&self) -> OidcProviderConfig {
+        OidcProviderConfig {
+            name: "stub".to_string(),
+            kind: OidcProviderKind::Oidc,
+            issuer: self.url.clone(),
+            client_id: CLIENT_ID.to_string(),
+            client_secret: String::new(),
+            redirect_uri: "http://localhost:3000/oidc/callback".to_string(),
+            scopes: "openid profile email".to_string(),
+            groups_claim: "groups".to_string(),
+            group_mapping: HashMap::from([("idp-admins".to_string(), "admin".to_string())]),
+        }
+    }
+}
+
+async fn discovery(State(issuer): State<Issuer>) -> Json<Value> {
+    Json(json!({
+        "issuer": issuer.url,
+        "authorization_endpoint": format!("{}/authorize", issuer.url),
+        "token_endpoint": format!("{}/token", issuer.url),
+        "jwks_uri": format!("{}/jwks", issuer.url),
+    }))
+}
+
+async fn jwks(State(issuer): State<Issuer>) -> Json<Value> {
+    Json(json!({
+        "keys": [{
+            "kty": "OKP",
+            "crv": "Ed25519",
+            "x": URL_SAFE_NO_PAD.encode(issuer.public_key.as_slice()),
+            "kid": KID,
+            "alg": "EdDSA",
+            "use": "sig",
+        }]
+    }))
+}
+
+async fn token(State(issuer): State<Issuer>) -> Json<Value> {
+    let mut header = Header::new(Algorithm::EdDSA);
+    header.kid = Some(KID.to_string());
+    let claims = issuer.claims.lock().unwrap().clone();
+    let id_token =
+        jsonwebtoken::encode(&header, &claims, &EncodingKey::from_ed_der(&issuer.key)).unwrap();
+
+    Json(json!({
+        "access_token": "stub-access-token",
+        "token_type": "Bearer",
+        "id_token": id_token,
+    }))
+}
+
+async fn setup(pool: PgPool) -> (TestServer, Issuer) {
+    let issuer = Issuer::start().await;
+    let mut config = common::config();
+    config.oidc = vec![issuer.provider()];
+    (common::server(pool, config).await, issuer)
+}
+
+/// 走完授权跳转和回调，`overrides` 覆盖 id_token 中的默认 claim
+async fn sign_in(server: &TestServer, issuer: &Issuer, overrides: Value) -> TestResponse {
+    let (state, nonce) = authorize(server).await;
+
+    let now = Utc::now().timestamp();
+    let mut claims = json!({
+        "iss": issuer.url,
+        "aud": CLIENT_ID,
+        "sub": "subject-1",
+        "iat": now,
+        "exp": now + 300,
+        "nonce": nonce,
+    });
+    for (key, value) in overrides.as_object().unwrap() {
+        claims[key] = value.clone();
+    }
+    *issuer.claims.lock().unwrap() = claims;
+
+    callback(server, &state).await
+}
+
+async fn authorize(server: &TestServer) -> (String, String) {
+    let response = server.get("/auth/oidc/stub/authorize").await;
+    response.assert_status_see_other();
+
+    let location = Url::parse(response.header("location").to_str().unwrap()).unwrap();
+    let param = |name: &str| {
+        location
+            .query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+            .unwrap()
+    };
+    assert_eq!(param("client_id"), CLIENT_ID);
+    assert_eq!(param("code_challenge_method"), "S256");
+
+    (param("state"), param("nonce"))
+}
+
+async fn callback(server: &TestServer, state: &str) -> TestResponse {
+    server
+        .get("/auth/oidc/stub/callback")
+        .add_query_param("code", "stub-code")
+        .add_query_param("state", state)
+        .await
+}
+
+async fn create_local_user(pool: &PgPool, username: &str, email: &str, verified: bool) -> User {
+    let mut user = User::new(username.to_string(), None);
+    user.email = Some(email.to_string());
+    user.email_verified_at = verified.then(Utc::now);
+    UserService::create(pool, &user).await.unwrap()
+}
+
+async fn group_names(pool: &PgPool, user_id: &str) -> Vec<String> {
+    sqlx::query_scalar(
+        r#"
+        SELECT g.name FROM groups g
+        JOIN user_groups ug ON ug.group_id = g.id
+        WHERE ug.user_id = $1::uuid
+        ORDER BY g.name
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .unwrap()
+}
+
+#[sqlx::test]
+async fn provisions_user_and_maps_groups(pool: PgPool) {
+    let (server, issuer) = setup(pool.clone()).await;
+
+    let response = sign_in(
+        &server,
+        &issuer,
+        json!({
+            "email": "alice@example.com",
+            "email_verified": true,
+            "preferred_username": "alice",
+            "groups": ["idp-admins", "unmapped"],
+        }),
+    )
+    .await;
+    response.assert_status_ok();
+
+    let body: Value = response.json();
+    assert!(body["token"].is_string());
+    assert_eq!(body["user"]["username"], "alice");
+    assert_eq!(body["user"]["email"], "alice@example.com");
+
+    let user_id = body["user"]["id"].as_str().unwrap();
+    assert_eq!(group_names(&pool, user_id).await, ["admin", "users"]);
+
+    // 再次登录使用同一账户，IdP 中移除的用户组同步移除
+    let again = sign_in(&server, &issuer, json!({ "groups": [] })).await;
+    again.assert_status_ok();
+    assert_eq!(again.json::<Value>()["user"]["id"], user_id);
+    assert_eq!(group_names(&pool, user_id).await, ["users"]);
+}
+
+#[sqlx::test]
+async fn links_local_user_with_verified_email(pool: PgPool) {
+    let (server, issuer) = setup(pool.clone()).await;
+    let carol = create_local_user(&pool, "carol", "carol@example.com", true).await;
+
+    let response = sign_in(
+        &server,
+        &issuer,
+        json!({ "email": "carol@example.com", "email_verified": true }),
+    )
+    .await;
+    response.assert_status_ok();
+    assert_eq!(response.json::<Value>()["user"]["id"], carol.id.to_string());
+}
+
+#[sqlx::test]
+async fn does_not_link_unverified_email(pool: PgPool) {
+    let (server, issuer) = setup(pool.clone()).await;
+    let carol = create_local_user(&pool, "carol", "carol@example.com", true).await;
+    let dave = create_local_user(&pool, "dave", "dave@example.com", false).await;
+
+    // IdP 未验证邮箱
+    let response = sign_in(
+        &server,
+        &issuer,
+        json!({ "sub": "subject-carol", "email": "carol@example.com", "email_verified": false }),
+    )
+    .await;
+    response.assert_status_ok();
+    let body: Value = response.json();
+    assert_ne!(body["user"]["id"], carol.id.to_string());
+    assert!(body["user"]["email"].is_null());
+
+    // 本地账户未验证邮箱
+    let response = sign_in(
+        &server,
+        &issuer,
+        json!({ "sub": "subject-dave", "email": "dave@example.com", "email_verified": true }),
+    )
+    .await;
+    response.assert_status_ok();
+    let body: Value = response.json();
+    assert_ne!(body["user"]["id"], dave.id.to_string());
+    assert!(body["user"]["email"].is_null());
+}
+
+#[sqlx::test]
+async fn rejects_nonce_mismatch(pool: PgPool) {
+    let (server, issuer) = setup(pool.clone()).await;
+
+    let response = sign_in(&server, &issuer, json!({ "nonce": "forged" })).await;
+    response.assert_status_unauthorized();
+    assert_eq!(response.json::<Value>()["code"], "unauthorized");
+
+    let identities: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM user_identities")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(identities, 0);
+}
+
+#[sqlx::test]
+async fn rejects_replayed_state(pool: PgPool) {
+    let (server, issuer) = setup(pool).await;
+    let (state, nonce) = authorize(&server).await;
+
+    let now = Utc::now().timestamp();
+    *issuer.claims.lock().unwrap() = json!({
+        "iss": issuer.url,
+        "aud": CLIENT_ID,
+        "sub": "subject-1",
+        "iat": now,
+        "exp": now + 300,
+        "nonce": nonce,
+    });
+
+    callback(&server, &state).await.assert_status_ok();
+
+    let replay = callback(&server, &state).await;
+    replay.assert_status_bad_request();
+    assert_eq!(replay.json::<Value>()["code"], "bad_request");
+}
+
+#[sqlx::test]
+async fn rejects_token_from_other_issuer(pool: PgPool) {
+    let (server, issuer) = setup(pool).await;
+
+    let response = sign_in(
+        &server,
+        &issuer,
+        json!({ "iss": "https://attacker.example.com" }),
+    )
+    .await;
+    response.assert_status_unauthorized();
+}
@@ -0,0 +1,320 @@
+mod common;
+
+use aws_lc_rs::{
+    rand::SystemRandom,
+    signature::{ECDSA_P256_SHA256_ASN1_SIGNING, EcdsaKeyPair, KeyPair},
+};
+use axum_test::{TestResponse, TestServer};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+
+const ORIGIN: &str = "http://localhost:3000";
+const RP_ID: &str = "localhost";
+
+/// authenticator data 中的标志位
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+const FLAG_ATTESTED_CREDENTIAL: u8 = 0x40;
+
+/// 最小的 CBOR 编码，只覆盖 attestation object 和 COSE 公钥用到的类型
+enum Cbor {
+    Int(i64),
+    Bytes(Vec<u8>),
+    Text(&'static str),
+    Map(Vec<(Cbor, Cbor)>),
+}
+
+impl Cbor {
+    fn encode(&self, out: &mut Vec<u8>) {
+        fn head(out: &mut Vec<u8>, major: u8, len: u64) {
+            match len {
+                0..=23 => out.push(major << 5 | len as u8),
+                24..=0xff => out.extend([major << 5 | 24, len as u8]),
+                _ => {
+                    out.push(major << 5 | 25);
+                    out.extend((len as u16).to_be_bytes());
+                }
+            }
+        }
+
+        match self {
+            Cbor::Int(n) if *n >= 0 => head(out, 0, *n as u64),
+            Cbor::Int(n) => head(out, 1, (-1 - n) as u64),
+            Cbor::Bytes(bytes) => {
+                head(out, 2, bytes.len() as u64);
+                out.extend(bytes);
+            }
+            Cbor::Text(text) => {
+                head(out, 3, text.len() as u64);
+                out.extend(text.as_bytes());
+            }
+            Cbor::Map(entries) => {
+                head(out, 5, entries.len() as u64);
+                for (key, value) in entries {
+                    key.encode(out);
+                    value.encode(out);
+                }
+            }
+        }
+    }
+
+    fn to_vec(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode(&mut out);
+        out
+    }
+}
+
+fn b64(data: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(data)
+}
+
+fn unb64(data: &Value) -> Vec<u8> {
+    URL_SAFE_NO_PAD
+        .decode(data.as_str().unwrap().trim_end_matches('='))
+        .unwrap()
+}
+
+/// 软件实现的通行密钥认证器，使用 ES256 和 `none` 证明
+struct SoftAuthenticator {
+    key: EcdsaKeyPair,
+    credential_id: Vec<u8>,
+    user_handle: Vec<u8>,
+    counter: u32,
+    origin: &'static str,
+}
+
+impl SoftAuthenticator {
+    fn new() -> Self {
+        Self {
+            key: EcdsaKeyPair::generate(&ECDSA_P256_SHA256_ASN1_SIGNING).unwrap(),
+            credential_id: Sha256::digest(uuid::Uuid::new_v4().as_bytes()).to_vec(),
+            user_handle: Vec::new(),
+            counter: 0,
+            origin: ORIGIN,
+        }
+    }
+
+    fn client_data(&self, kind: &str, options: &Value) -> Vec<u8> {
+        let challenge = unb64(&options["publicKey"]["challenge"]);
+        serde_json::to_vec(&json!({
+            "type": kind,
+            "challenge": b64(&challenge),
+            "origin": self.origin,
+            "crossOrigin": false,
+        }))
+        .unwrap()
+    }
+
+    fn authenticator_data(&self, flags: u8) -> Vec<u8> {
+        let mut data = Sha256::digest(RP_ID.as_bytes()).to_vec();
+        data.push(flags);
+        data.extend(self.counter.to_be_bytes());
+        data
+    }
+
+    /// 响应 `navigator.credentials.create()`
+    fn register(&mut self, options: &Value) -> Value {
+        self.user_handle = unb64(&options["publicKey"]["user"]["id"]);
+
+        // 未压缩的 P-256 公钥: 0x04 || x || y
+        let public_key = self.key.public_key().as_ref();
+        let cose_key = Cbor::Map(vec![
+            (Cbor::Int(1), Cbor::Int(2)),
+            (Cbor::Int(3), Cbor::Int(-7)),
+            (Cbor::Int(-1), Cbor::Int(1)),
+            (Cbor::Int(-2), Cbor::Bytes(public_key[1..33].to_vec())),
+            (Cbor::Int(-3), Cbor::Bytes(public_key[33..65].to_vec())),
+        ]);
+
+        let mut auth_data = self
+            .authenticator_data(FLAG_USER_PRESENT | FLAG_USER_VERIFIED | FLAG_ATTESTED_CREDENTIAL);
+        auth_data.extend([0u8; 16]);
+        auth_data.extend((self.credential_id.len() as u16).to_be_bytes());
+        auth_data.extend(&self.credential_id);
+        auth_data.extend(cose_key.to_vec());
+
+        let attestation_object = Cbor::Map(vec![
+            (Cbor::Text("fmt"), Cbor::Text("none")),
+            (Cbor::Text("attStmt"), Cbor::Map(Vec::new())),
+            (Cbor::Text("authData"), Cbor::Bytes(auth_data)),
+        ]);
+
+        json!({
+            "id": b64(&self.credential_id),
+            "rawId": b64(&self.credential_id),
+            "type": "public-key",
+            "response": {
+                "attestationObject": b64(&attestation_object.to_vec()),
+                "clientDataJSON": b64(&self.client_data("webauthn.create", options)),
+            },
+            "extensions": {},
+        })
+    }
+
+    /// 响应 `navigator.credentials.get()`
+    fn assert(&mut self, options: &Value) -> Value {
+        self.counter += 1;
+        let client_data = self.client_data("webauthn.get", options);
+        let auth_data = self.authenticator_data(FLAG_USER_PRESENT | FLAG_USER_VERIFIED);
+
+        let mut signed = auth_data.clone();
+        signed.extend(Sha256::digest(&client_data));
+        let signature = self.key.sign(&SystemRandom::new(), &signed).unwrap();
+
+        json!({
+            "id": b64(&self.credential_id),
+            "rawId": b64(&self.credential_id),
+            "type": "public-key",
+            "response": {
+                "authenticatorData": b64(&auth_data),
+                "clientDataJSON": b64(&client_data),
+                "signature": b64(signature.as_ref()),
+                "userHandle": b64(&self.user_handle),
+            },
+            "extensions": {},
+        })
+    }
+}
+
+async fn admin_session(server: &TestServer) -> (String, String) {
+    let body = common::login(server, "admin", common::ADMIN_PASSWORD).await;
+    (
+        body["token"].as_str().unwrap().to_string(),
+        body["user"]["id"].as_str().unwrap().to_string(),
+    )
+}
+
+async fn register(server: &TestServer, token: &str, authenticator: &mut SoftAuthenticator) {
+    let start: Value = server
+        .post("/auth/passkeys/register/start")
+        .authorization_bearer(token)
+        .await
+        .json();
+    let selection = &start["options"]["publicKey"]["authenticatorSelection"];
+    assert_eq!(selection["residentKey"], "required");
+
+    server
+        .post("/auth/passkeys/register/finish")
+        .authorization_bearer(token)
+        .json(&json!({
+            "challenge_id": start["challenge_id"],
+            "name": "Test key",
+            "credential": authenticator.register(&start["options"]),
+        }))
+        .await
+        .assert_status(axum::http::StatusCode::CREATED);
+}
+
+/// 不带用户名，走可发现凭证流程
+async fn discoverable_login(
+    server: &TestServer,
+    authenticator: &mut SoftAuthenticator,
+) -> (Value, TestResponse) {
+    let start: Value = server
+        .post("/auth/passkeys/login/start")
+        .json(&json!({}))
+        .await
+        .json();
+    let allowed = &start["options"]["publicKey"]["allowCredentials"];
+    assert!(allowed.as_array().is_none_or(Vec::is_empty));
+
+    let credential = authenticator.assert(&start["options"]);
+    let response = server
+        .post("/auth/passkeys/login/finish")
+        .json(&json!({ "challenge_id": start["challenge_id"], "credential": credential }))
+        .await;
+
+    (
+        json!({ "challenge_id": start["challenge_id"], "credential": credential }),
+        response,
+    )
+}
+
+#[sqlx::test]
+async fn registers_and_signs_in_with_discoverable_passkey(pool: PgPool) {
+    let server = common::server(pool, common::config()).await;
+    let (token, user_id) = admin_session(&server).await;
+    let mut authenticator = SoftAuthenticator::new();
+
+    register(&server, &token, &mut authenticator).await;
+
+    let passkeys: Value = server
+        .get("/auth/passkeys")
+        .authorization_bearer(&token)
+        .await
+        .json();
+    assert_eq!(passkeys.as_array().unwrap().len(), 1);
+    assert_eq!(passkeys[0]["name"], "Test key");
+
+    let (_, response) = discoverable_login(&server, &mut authenticator).await;
+    response.assert_status_ok();
+    let body: Value = response.json();
+    assert!(body["token"].is_string());
+    assert_eq!(body["user"]["id"], user_id);
+
+    // 签名计数器随登录更新
+    let (_, response) = discoverable_login(&server, &mut authenticator).await;
+    response.assert_status_ok();
+}
+
+#[sqlx::test]
+async fn login_start_does_not_reveal_usernames(pool: PgPool) {
+    let server = common::server(pool, common::config()).await;
+    let (token, _) = admin_session(&server).await;
+    register(&server, &token, &mut SoftAuthenticator::new()).await;
+
+    // 已注册通行密钥的用户和不存在的用户得到同样的可发现凭证选项
+    for username in ["admin", "nobody"] {
+        let start: Value = server
+            .post("/auth/passkeys/login/start")
+            .json(&json!({ "username": username }))
+            .await
+            .json();
+        let allowed = &start["options"]["publicKey"]["allowCredentials"];
+        assert!(allowed.as_array().is_none_or(Vec::is_empty));
+        assert_eq!(start["options"]["mediation"], "conditional");
+    }
+}
+
+#[sqlx::test]
+async fn rejects_replayed_assertion(pool: PgPool) {
+    let server = common::server(pool, common::config()).await;
+    let (token, _) = admin_session(&server).await;
+    let mut authenticator = SoftAuthenticator::new();
+    register(&server, &token, &mut authenticator).await;
+
+    let (request, response) = discoverable_login(&server, &mut authenticator).await;
+    response.assert_status_ok();
+
+    let replay = server
+        .post("/auth/passkeys/login/finish")
+        .json(&request)
+        .await;
+    replay.assert_status_bad_request();
+}
+
+#[sqlx::test]
+async fn rejects_assertion_from_other_origin(pool: PgPool) {
+    let server = common::server(pool, common::config()).await;
+    let (token, _) = admin_session(&server).await;
+    let mut authenticator = SoftAuthenticator::new();
+    register(&server, &token, &mut authenticator).await;
+
+    authenticator.origin = "https://phishing.example.com";
+    let (_, response) = discoverable_login(&server, &mut authenticator).await;
+    response.assert_status_unauthorized();
+    assert_eq!(response.json::<Value>()["code"], "invalid_credentials");
+}
+
+#[sqlx::test]
+async fn rejects_unregistered_credential(pool: PgPool) {
+    let server = common::server(pool, common::config()).await;
+    let mut authenticator = SoftAuthenticator::new();
+    authenticator.user_handle = uuid::Uuid::new_v4().as_bytes().to_vec();
+
+    let (_, response) = discoverable_login(&server, &mut authenticator).await;
+    response.assert_status_unauthorized();
+}
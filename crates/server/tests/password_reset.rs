@@ -0,0 +1,90 @@
+mod common;
+
+use axum_test::{TestResponse, TestServer};
+use serde_json::json;
+use sqlx::PgPool;
+
+const EMAIL: &str = "bob@example.com";
+const OLD_PASSWORD: &str = "Old-Passw0rd";
+const NEW_PASSWORD: &str = "New-Passw0rd";
+
+async fn setup(pool: &PgPool) -> (TestServer, common::Mailbox) {
+    let mailbox = common::Mailbox::new();
+    let mut config = common::config();
+    mailbox.configure(&mut config);
+    let server = common::server(pool.clone(), config).await;
+    common::create_user(pool, "bob", OLD_PASSWORD, Some(EMAIL)).await;
+    (server, mailbox)
+}
+
+async fn forgot(server: &TestServer, email: &str) {
+    server
+        .post("/auth/password/forgot")
+        .json(&json!({ "email": email }))
+        .await
+        .assert_status(axum::http::StatusCode::ACCEPTED);
+}
+
+async fn reset(server: &TestServer, token: &str, password: &str) -> TestResponse {
+    server
+        .post("/auth/password/reset")
+        .json(&json!({ "token": token, "new_password": password }))
+        .await
+}
+
+#[sqlx::test]
+async fn resets_password_once_with_emailed_token(pool: PgPool) {
+    let (server, mailbox) = setup(&pool).await;
+
+    forgot(&server, EMAIL).await;
+    let token = mailbox.next_token().await;
+
+    reset(&server, &token, NEW_PASSWORD)
+        .await
+        .assert_status(axum::http::StatusCode::NO_CONTENT);
+    assert!(common::login(&server, "bob", NEW_PASSWORD).await["token"].is_string());
+    assert_eq!(
+        common::login(&server, "bob", OLD_PASSWORD).await["code"],
+        "invalid_credentials"
+    );
+
+    // 令牌只能使用一次
+    common::assert_validation_error(&reset(&server, &token, "Another-Passw0rd").await, "token");
+}
+
+#[sqlx::test]
+async fn rejects_expired_token(pool: PgPool) {
+    let (server, mailbox) = setup(&pool).await;
+
+    forgot(&server, EMAIL).await;
+    let token = mailbox.next_token().await;
+    sqlx::query("UPDATE password_reset_tokens SET expires_at = NOW() - INTERVAL '1 second'")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    common::assert_validation_error(&reset(&server, &token, NEW_PASSWORD).await, "token");
+    assert!(common::login(&server, "bob", OLD_PASSWORD).await["token"].is_string());
+}
+
+#[sqlx::test]
+async fn rejects_weak_password_without_consuming_token(pool: PgPool) {
+    let (server, mailbox) = setup(&pool).await;
+
+    forgot(&server, EMAIL).await;
+    let token = mailbox.next_token().await;
+
+    common::assert_validation_error(&reset(&server, &token, "password").await, "new_password");
+
+    reset(&server, &token, NEW_PASSWORD)
+        .await
+        .assert_status(axum::http::StatusCode::NO_CONTENT);
+}
+
+#[sqlx::test]
+async fn does_not_reveal_unknown_email(pool: PgPool) {
+    let (server, mailbox) = setup(&pool).await;
+
+    forgot(&server, "nobody@example.com").await;
+    mailbox.assert_empty().await;
+}
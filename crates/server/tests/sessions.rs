@@ -0,0 +1,80 @@
+mod common;
+
+use std::time::Duration;
+
+use axum_test::TestServer;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+
+const PASSWORD: &str = "Old-Passw0rd";
+const NEW_PASSWORD: &str = "New-Passw0rd";
+
+async fn token(server: &TestServer) -> String {
+    common::login(server, "bob", PASSWORD).await["token"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+/// 令牌的签发时间精确到秒，等待进入下一秒再修改密码
+async fn next_second() {
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+}
+
+async fn assert_revoked(server: &TestServer, token: &str) {
+    let response = server.get("/auth/me").authorization_bearer(token).await;
+    response.assert_status_unauthorized();
+    assert_eq!(response.json::<Value>()["code"], "session_revoked");
+}
+
+#[sqlx::test]
+async fn password_change_revokes_existing_sessions(pool: PgPool) {
+    let server = common::server(pool.clone(), common::config()).await;
+    common::create_user(&pool, "bob", PASSWORD, None).await;
+    let other_session = token(&server).await;
+    let session = token(&server).await;
+    next_second().await;
+
+    let response = server
+        .put("/auth/password")
+        .authorization_bearer(&session)
+        .json(&json!({ "current_password": PASSWORD, "new_password": NEW_PASSWORD }))
+        .await;
+    response.assert_status_ok();
+    let new_token = response.json::<Value>()["token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    assert_revoked(&server, &session).await;
+    assert_revoked(&server, &other_session).await;
+    server
+        .get("/auth/me")
+        .authorization_bearer(&new_token)
+        .await
+        .assert_status_ok();
+}
+
+#[sqlx::test]
+async fn password_reset_revokes_existing_sessions(pool: PgPool) {
+    let mailbox = common::Mailbox::new();
+    let mut config = common::config();
+    mailbox.configure(&mut config);
+    let server = common::server(pool.clone(), config).await;
+    common::create_user(&pool, "bob", PASSWORD, Some("bob@example.com")).await;
+    let session = token(&server).await;
+    next_second().await;
+
+    server
+        .post("/auth/password/forgot")
+        .json(&json!({ "email": "bob@example.com" }))
+        .await;
+    server
+        .post("/auth/password/reset")
+        .json(&json!({ "token": mailbox.next_token().await, "new_password": NEW_PASSWORD }))
+        .await
+        .assert_status(axum::http::StatusCode::NO_CONTENT);
+
+    assert_revoked(&server, &session).await;
+    assert!(common::login(&server, "bob", NEW_PASSWORD).await["token"].is_string());
+}
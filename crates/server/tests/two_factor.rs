@@ -0,0 +1,90 @@
+mod common;
+
+use axum_test::{TestResponse, TestServer};
+use serde_json::{Value, json};
+use server::services::{TotpService, UserService};
+use sqlx::PgPool;
+
+/// 测试中不会使用有效验证码，只验证失败计数
+const WRONG_CODE: &str = "000000";
+
+async fn enable_totp(pool: &PgPool, username: &str) {
+    let user = UserService::find_by_username(pool, username)
+        .await
+        .unwrap()
+        .unwrap();
+    TotpService::begin_enrollment(pool, &user).await.unwrap();
+    TotpService::enable(pool, user.id).await.unwrap();
+}
+
+async fn password_login(server: &TestServer) -> TestResponse {
+    server
+        .post("/auth/login")
+        .json(&json!({ "username": "admin", "password": common::ADMIN_PASSWORD }))
+        .await
+}
+
+fn assert_rate_limited(response: &TestResponse) {
+    response.assert_status(axum::http::StatusCode::TOO_MANY_REQUESTS);
+    let body: Value = response.json();
+    assert_eq!(body["code"], "rate_limited");
+    assert!(body["retry_after"].as_i64().unwrap() > 0);
+}
+
+#[sqlx::test]
+async fn password_login_keeps_second_factor_failures(pool: PgPool) {
+    let server = common::server(pool.clone(), common::config()).await;
+    enable_totp(&pool, "admin").await;
+
+    // 每轮重新输入正确密码取得新的挑战，再猜错一次验证码
+    for _ in 0..2 {
+        let response = password_login(&server).await;
+        response.assert_status_ok();
+        let body: Value = response.json();
+        assert_eq!(body["mfa_required"], true);
+
+        let response = server
+            .post("/auth/login/2fa")
+            .json(&json!({ "mfa_token": body["mfa_token"], "code": WRONG_CODE }))
+            .await;
+        response.assert_status_unauthorized();
+        assert_eq!(response.json::<Value>()["code"], "invalid_mfa_code");
+    }
+
+    // 密码正确不会清除待完成的第二因素失败计数
+    assert_rate_limited(&password_login(&server).await);
+}
+
+#[sqlx::test]
+async fn limits_second_factor_guesses_with_session(pool: PgPool) {
+    let server = common::server(pool.clone(), common::config()).await;
+    let token = common::login(&server, "admin", common::ADMIN_PASSWORD).await["token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    enable_totp(&pool, "admin").await;
+
+    for _ in 0..2 {
+        let response = server
+            .post("/auth/2fa/totp/disable")
+            .authorization_bearer(&token)
+            .json(&json!({ "code": WRONG_CODE }))
+            .await;
+        response.assert_status_unauthorized();
+    }
+
+    // 关闭两步验证和重新生成恢复码共用同一计数
+    let response = server
+        .post("/auth/2fa/recovery-codes")
+        .authorization_bearer(&token)
+        .json(&json!({ "code": WRONG_CODE }))
+        .await;
+    assert_rate_limited(&response);
+
+    let status: Value = server
+        .get("/auth/2fa")
+        .authorization_bearer(&token)
+        .await
+        .json();
+    assert_eq!(status["enabled"], true);
+}